@@ -3,6 +3,8 @@ use core::net::{IpAddr, Ipv4Addr, SocketAddr};
 use lightyear::netcode::Key;
 use lightyear::prelude::client::*;
 use lightyear::prelude::*;
+use shared::gameplay::balance::Balance;
+use shared::protocol::*;
 use shared::*;
 use std::net::SocketAddrV4;
 
@@ -19,11 +21,35 @@ pub struct ClientPlugin;
 
 impl Plugin for ClientPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<NetworkError>();
         app.add_systems(Startup, startup);
-        app.add_systems(Update, (send_ping, handle_pong));
+        app.add_systems(
+            Update,
+            (
+                send_client_hello,
+                handle_version_mismatch,
+                send_ping,
+                handle_pong,
+                handle_balance_update,
+                handle_match_state,
+                handle_match_started,
+                handle_match_ended,
+                handle_command_rejected,
+                handle_gameplay_events,
+                handle_chat,
+            ),
+        );
     }
 }
 
+/// A connection problem to show to the player. `recoverable` is false when
+/// retrying cannot help, e.g. after a protocol version mismatch.
+#[derive(Event)]
+pub struct NetworkError {
+    pub error: String,
+    pub recoverable: bool,
+}
+
 const CLIENT_ADDR: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 4000);
 
 fn startup(mut commands: Commands) {
@@ -33,7 +59,7 @@ fn startup(mut commands: Commands) {
         server_addr: SocketAddr::V4(SocketAddrV4::new(addr, 7777)),
         client_id: 1,
         private_key: Key::default(),
-        protocol_id: 15,
+        protocol_id: NETCODE_PROTOCOL_ID,
     };
 
     let client = commands
@@ -51,6 +77,24 @@ fn startup(mut commands: Commands) {
     commands.trigger_targets(Connect, client);
 }
 
+fn handle_version_mismatch(
+    mut receiver: Query<&mut MessageReceiver<VersionMismatch>>,
+    mut network_errors: EventWriter<NetworkError>,
+) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            error!(
+                "Protocol version mismatch: server {:016x}, client {:016x}",
+                message.server, message.client
+            );
+            network_errors.write(NetworkError {
+                error: "This game version is out of date, please update to play".to_string(),
+                recoverable: false,
+            });
+        }
+    }
+}
+
 fn send_ping(
     mut timer: Local<Timer>,
     time: Res<Time>,
@@ -65,7 +109,7 @@ fn send_ping(
     if timer.just_finished() {
         for mut sender in sender.iter_mut() {
             let ping = PingMessage("Hello from client!".to_string());
-            sender.send::<GameNetworkChannel>(ping);
+            sender.send::<PingChannel>(ping);
             info!("Sent ping message");
         }
     }
@@ -77,4 +121,68 @@ fn handle_pong(mut receiver: Query<&mut MessageReceiver<PingMessage>>) {
             info!("Received from server: {}", message.0);
         }
     }
-}
\ No newline at end of file
+}
+
+fn handle_balance_update(
+    mut receiver: Query<&mut MessageReceiver<BalanceUpdate>>,
+    mut balance: ResMut<Balance>,
+) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            info!("Received balance from server");
+            *balance = message.0;
+        }
+    }
+}
+
+fn handle_match_state(mut receiver: Query<&mut MessageReceiver<MatchStateChanged>>) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            info!("Match state: {:?}", message.state);
+        }
+    }
+}
+
+fn handle_match_started(mut receiver: Query<&mut MessageReceiver<MatchStarted>>) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            info!("Match {} started, playing as team {}", message.match_id, message.team);
+        }
+    }
+}
+
+fn handle_match_ended(mut receiver: Query<&mut MessageReceiver<MatchEnded>>) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            if message.winners.is_empty() {
+                info!("Match ended in a draw");
+            } else {
+                info!("Match ended, teams {:?} win", message.winners);
+            }
+        }
+    }
+}
+
+fn handle_command_rejected(mut receiver: Query<&mut MessageReceiver<CommandRejected>>) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            warn!("Server rejected command: {}", message.reason);
+        }
+    }
+}
+
+fn handle_gameplay_events(mut receiver: Query<&mut MessageReceiver<GameplayEvent>>) {
+    for mut receiver in receiver.iter_mut() {
+        for event in receiver.receive() {
+            info!("Gameplay event: {:?}", event);
+        }
+    }
+}
+
+fn handle_chat(mut receiver: Query<&mut MessageReceiver<ChatBroadcast>>) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            info!("[team {}] {}: {}", message.team, message.player_id, message.text);
+        }
+    }
+}
@@ -96,11 +96,8 @@ pub struct SyncCompleted {
     pub conflicts: Vec<String>,
 }
 
-#[derive(Event)]
-pub struct NetworkError {
-    pub error: String,
-    pub recoverable: bool,
-}
+// Shared with the game connection, which reports version mismatches through it
+pub use crate::client_logic::NetworkError;
 
 // Supporting Types
 #[derive(Debug, Clone, Serialize, Deserialize)]
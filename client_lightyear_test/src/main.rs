@@ -3,6 +3,7 @@ use core::net::{IpAddr, Ipv4Addr, SocketAddr};
 use lightyear::netcode::Key;
 use lightyear::prelude::client::*;
 use lightyear::prelude::*;
+use shared::protocol::*;
 use shared::*;
 use std::net::SocketAddrV4;
 
@@ -28,7 +29,10 @@ pub struct ClientPlugin;
 impl Plugin for ClientPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, startup);
-        app.add_systems(Update, (send_ping, handle_pong));
+        app.add_systems(
+            Update,
+            (send_client_hello, handle_version_mismatch, send_ping, handle_pong),
+        );
     }
 }
 
@@ -39,7 +43,7 @@ fn startup(mut commands: Commands) {
         server_addr: SocketAddr::V4(SocketAddrV4::new(addr, 7777)),
         client_id: 1,
         private_key: Key::default(),
-        protocol_id: 15,
+        protocol_id: NETCODE_PROTOCOL_ID,
     };
 
     let client = commands
@@ -57,6 +61,17 @@ fn startup(mut commands: Commands) {
     commands.trigger_targets(Connect, client);
 }
 
+fn handle_version_mismatch(mut receiver: Query<&mut MessageReceiver<VersionMismatch>>) {
+    for mut receiver in receiver.iter_mut() {
+        for message in receiver.receive() {
+            error!(
+                "Protocol version mismatch: server {:016x}, client {:016x}",
+                message.server, message.client
+            );
+        }
+    }
+}
+
 fn send_ping(
     mut timer: Local<Timer>,
     time: Res<Time>,
@@ -71,7 +86,7 @@ fn send_ping(
     if timer.just_finished() {
         for mut sender in sender.iter_mut() {
             let ping = PingMessage("Hello from client!".to_string());
-            sender.send::<GameNetworkChannel>(ping);
+            sender.send::<PingChannel>(ping);
             info!("Sent ping message");
         }
     }
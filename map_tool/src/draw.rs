@@ -0,0 +1,199 @@
+use bevy::math::Vec2;
+use shared::gameplay::{
+    map::{Map, MapNode, NodeType, Terrain},
+    structures::StructureType,
+};
+use std::fmt::Write;
+
+const ASCII_WIDTH: usize = 72;
+const ASCII_HEIGHT: usize = 28;
+const SVG_SIZE: f32 = 800.0;
+const SVG_MARGIN: f32 = 40.0;
+
+/// Nodes sorted by id, with the bounding box of their positions. Nodes with a
+/// non-finite position cannot be placed and are left out of the box.
+fn layout(map: &Map) -> (Vec<&MapNode>, Vec2, Vec2) {
+    let mut nodes: Vec<&MapNode> = map.nodes.values().collect();
+    nodes.sort_by_key(|node| node.id);
+    let positions = || {
+        nodes
+            .iter()
+            .map(|node| node.position)
+            .filter(|position| position.is_finite())
+    };
+    let min = positions().fold(Vec2::splat(f32::MAX), Vec2::min);
+    let max = positions().fold(Vec2::splat(f32::MIN), Vec2::max).max(min);
+    (nodes, min, max)
+}
+
+fn symbol(node_type: &NodeType) -> char {
+    match node_type {
+        NodeType::Waypoint => 'o',
+        NodeType::StructureType(StructureType::Tower(None)) => 'T',
+        NodeType::StructureType(StructureType::Tower(Some(_))) => 't',
+        NodeType::StructureType(StructureType::BaseTower(_)) => 'B',
+    }
+}
+
+fn describe(node: &MapNode) -> String {
+    match &node.node_type {
+        NodeType::Waypoint => "waypoint".to_string(),
+        NodeType::StructureType(StructureType::Tower(None)) if node.neutral.garrison > 0 => {
+            format!("neutral tower, {} mana", node.neutral.garrison)
+        }
+        NodeType::StructureType(StructureType::Tower(None)) => "neutral tower".to_string(),
+        NodeType::StructureType(StructureType::Tower(Some(team))) => {
+            format!("tower of team {}", team)
+        }
+        NodeType::StructureType(StructureType::BaseTower(team)) => {
+            format!("base of team {}", team)
+        }
+    }
+}
+
+pub fn ascii(map: &Map) -> String {
+    let (nodes, min, max) = layout(map);
+    let mut out = format!("{}\n", map.name);
+    if nodes.is_empty() {
+        out += "(empty map)\n";
+        return out;
+    }
+
+    let size = (max - min).max(Vec2::ONE);
+    // Screen rows grow downwards, map y grows upwards
+    let to_cell = |position: Vec2| {
+        if !position.is_finite() {
+            return None;
+        }
+        let t = (position - min) / size;
+        let column = (t.x * (ASCII_WIDTH - 1) as f32).round() as usize;
+        let row = ((1.0 - t.y) * (ASCII_HEIGHT - 1) as f32).round() as usize;
+        Some((column.min(ASCII_WIDTH - 1), row.min(ASCII_HEIGHT - 1)))
+    };
+
+    let mut grid = vec![vec![' '; ASCII_WIDTH]; ASCII_HEIGHT];
+    for node in &nodes {
+        for &to in &node.connected_to {
+            let Some(other) = map.get_node(to) else {
+                continue;
+            };
+            let (Some((x0, y0)), Some((x1, y1))) =
+                (to_cell(node.position), to_cell(other.position))
+            else {
+                continue;
+            };
+            let steps = x0.abs_diff(x1).max(y0.abs_diff(y1)).max(1);
+            for step in 0..=steps {
+                let t = step as f32 / steps as f32;
+                let x = (x0 as f32 + (x1 as f32 - x0 as f32) * t).round() as usize;
+                let y = (y0 as f32 + (y1 as f32 - y0 as f32) * t).round() as usize;
+                grid[y][x] = '.';
+            }
+        }
+    }
+    for node in &nodes {
+        if let Some((x, y)) = to_cell(node.position) {
+            grid[y][x] = symbol(&node.node_type);
+        }
+    }
+
+    for row in grid {
+        out += row.iter().collect::<String>().trim_end();
+        out.push('\n');
+    }
+    out += "\nB base  t owned tower  T neutral tower  o waypoint\n\n";
+    for node in nodes {
+        let _ = writeln!(
+            out,
+            "{:>4} {:<26} ({:.0}, {:.0}) -> {:?}",
+            node.id,
+            describe(node),
+            node.position.x,
+            node.position.y,
+            node.connected_to
+        );
+    }
+    out
+}
+
+pub fn svg(map: &Map) -> String {
+    let (nodes, min, max) = layout(map);
+    let size = (max - min).max(Vec2::ONE);
+    let scale = (SVG_SIZE - 2.0 * SVG_MARGIN) / size.x.max(size.y);
+    let to_screen = |position: Vec2| {
+        Vec2::new(
+            SVG_MARGIN + (position.x - min.x) * scale,
+            SVG_SIZE - SVG_MARGIN - (position.y - min.y) * scale,
+        )
+    };
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n",
+        size = SVG_SIZE
+    );
+    let _ = writeln!(out, "  <title>{}</title>", escape(&map.name));
+    let _ = writeln!(out, "  <rect width=\"100%\" height=\"100%\" fill=\"#1e1e24\"/>");
+
+    for edge in &map.edges {
+        let (Some(from), Some(to)) = (map.get_node(edge.from), map.get_node(edge.to)) else {
+            continue;
+        };
+        if !from.position.is_finite() || !to.position.is_finite() {
+            continue;
+        }
+        let a = to_screen(from.position);
+        let b = to_screen(to.position);
+        let colour = match edge.terrain {
+            Terrain::Road => "#888888",
+            Terrain::Swamp => "#6b8e23",
+            Terrain::Bridge => "#c49a6c",
+        };
+        // One-way edges are dashed, running from `from` to `to`
+        let dash = if edge.one_way {
+            " stroke-dasharray=\"10 6\""
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            out,
+            "  <line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"3\"{}/>",
+            a.x, a.y, b.x, b.y, colour, dash
+        );
+    }
+
+    for node in nodes.iter().filter(|node| node.position.is_finite()) {
+        let p = to_screen(node.position);
+        let (radius, fill) = match node.node_type {
+            NodeType::Waypoint => (6.0, "#aaaaaa"),
+            NodeType::StructureType(StructureType::Tower(None)) => (14.0, "#d0d0d0"),
+            NodeType::StructureType(StructureType::Tower(Some(_))) => (14.0, "#6fa8dc"),
+            NodeType::StructureType(StructureType::BaseTower(_)) => (20.0, "#e06666"),
+        };
+        let _ = writeln!(
+            out,
+            "  <circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"{}\"><title>{} ({})</title></circle>",
+            p.x,
+            p.y,
+            radius,
+            fill,
+            node.id,
+            describe(node)
+        );
+        let _ = writeln!(
+            out,
+            "  <text x=\"{:.1}\" y=\"{:.1}\" fill=\"#ffffff\" font-family=\"monospace\" font-size=\"14\">{}</text>",
+            p.x + radius + 4.0,
+            p.y - radius,
+            node.id
+        );
+    }
+
+    out += "</svg>\n";
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
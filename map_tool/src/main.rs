@@ -0,0 +1,380 @@
+use anyhow::{Context, anyhow, bail};
+use bevy::math::Vec2;
+use ron::ser::PrettyConfig;
+use shared::gameplay::{
+    map::{EXAMPLE_MAP, Map, MapEdge, MapFile, MapNode, NodeId, NodeType, Terrain},
+    map_gen::{MapGenParams, generate_map},
+    structures::{NeutralGarrison, StructureType},
+};
+use std::env;
+use std::path::Path;
+
+mod draw;
+
+const USAGE: &str = "Usage: map_tool <command> [args]
+
+Commands:
+  new <file> <name>                       Create an empty map
+  add-node <file> <id> <x> <y> [type]     Add a node (type defaults to waypoint)
+  remove-node <file> <id>                 Remove a node and all its edges
+  add-edge <file> <a> <b>                 Connect two nodes in both directions
+  remove-edge <file> <a> <b>              Disconnect two nodes
+  set-edge <file> <a> <b> <terrain> [--length <l>] [--one-way]
+                                          Set edge attributes, one-way edges run from a to b
+  set-type <file> <id> <type>             Change a node's type
+  set-neutral <file> <id> <garrison> [regen] [cap] [--raid-below <mana>]
+                                          Configure a neutral tower's garrison and regen
+  validate <file>                         Report every problem with the map
+  analyze <file> [--strict]               Print the fairness report
+  generate <file> <seed> [teams] [nodes-per-team] [density]
+                                          Write a procedurally generated map
+  export-example <file>                   Write the built-in EXAMPLE_MAP to disk
+  to-const <file>                         Print the map as a Rust `MapData` constant
+  draw <file> [--svg]                     Print the map as ASCII art or SVG
+
+Node types: waypoint, tower, tower:<team>, base:<team>
+Terrains: road, swamp, bridge";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("❌ {:#}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> anyhow::Result<()> {
+    let Some(command) = args.first() else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+    let arg = |index: usize, name: &str| -> anyhow::Result<&str> {
+        args.get(index)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("Missing argument <{}>\n\n{}", name, USAGE))
+    };
+
+    match command.as_str() {
+        "new" => {
+            let map = MapFile {
+                name: arg(2, "name")?.to_string(),
+                nodes: Vec::new(),
+                edges: Vec::new(),
+            };
+            save(arg(1, "file")?, &map)
+        }
+        "add-node" => {
+            let file = arg(1, "file")?;
+            let mut map = load(file)?;
+            let id = parse_id(arg(2, "id")?)?;
+            if map.nodes.iter().any(|node| node.id == id) {
+                bail!("Node {} already exists", id);
+            }
+            let node_type = match args.get(5) {
+                Some(node_type) => parse_node_type(node_type)?,
+                None => NodeType::Waypoint,
+            };
+            map.nodes.push(MapNode {
+                id,
+                connected_to: Vec::new(),
+                position: Vec2::new(parse_f32(arg(3, "x")?)?, parse_f32(arg(4, "y")?)?),
+                node_type,
+                neutral: NeutralGarrison::NONE,
+            });
+            save(file, &map)
+        }
+        "remove-node" => {
+            let file = arg(1, "file")?;
+            let mut map = load(file)?;
+            let id = parse_id(arg(2, "id")?)?;
+            let before = map.nodes.len();
+            map.nodes.retain(|node| node.id != id);
+            if map.nodes.len() == before {
+                bail!("Node {} does not exist", id);
+            }
+            for node in &mut map.nodes {
+                node.connected_to.retain(|&connected| connected != id);
+            }
+            map.edges.retain(|edge| edge.from != id && edge.to != id);
+            save(file, &map)
+        }
+        "add-edge" | "remove-edge" => {
+            let file = arg(1, "file")?;
+            let mut map = load(file)?;
+            let a = parse_id(arg(2, "a")?)?;
+            let b = parse_id(arg(3, "b")?)?;
+            for (from, to) in [(a, b), (b, a)] {
+                let node = find_node(&mut map, from)?;
+                if command == "add-edge" {
+                    if !node.connected_to.contains(&to) {
+                        node.connected_to.push(to);
+                        node.connected_to.sort_unstable();
+                    }
+                } else {
+                    node.connected_to.retain(|&connected| connected != to);
+                }
+            }
+            if command == "remove-edge" {
+                map.edges.retain(|edge| !edge.joins(a, b));
+            } else {
+                // Both nodes now list each other, so the connection is two-way
+                for edge in map.edges.iter_mut().filter(|edge| edge.joins(a, b)) {
+                    edge.one_way = false;
+                }
+            }
+            save(file, &map)
+        }
+        "set-edge" => {
+            let file = arg(1, "file")?;
+            let mut map = load(file)?;
+            let from = parse_id(arg(2, "a")?)?;
+            let to = parse_id(arg(3, "b")?)?;
+            let length = match args.iter().position(|arg| arg == "--length") {
+                Some(index) => Some(parse_f32(arg(index + 1, "length")?)?),
+                None => None,
+            };
+            let edge = MapEdge {
+                from,
+                to,
+                terrain: parse_terrain(arg(4, "terrain")?)?,
+                length,
+                one_way: args.iter().any(|arg| arg == "--one-way"),
+            };
+
+            find_node(&mut map, from)?;
+            find_node(&mut map, to)?;
+            // Re-link both nodes from scratch so a changed direction takes effect
+            map.edges.retain(|other| !other.joins(from, to));
+            for (a, b) in [(from, to), (to, from)] {
+                find_node(&mut map, a)?
+                    .connected_to
+                    .retain(|&connected| connected != b);
+            }
+            map.edges.push(edge);
+            save(file, &map)
+        }
+        "set-type" => {
+            let file = arg(1, "file")?;
+            let mut map = load(file)?;
+            let node_type = parse_node_type(arg(3, "type")?)?;
+            let node = find_node(&mut map, parse_id(arg(2, "id")?)?)?;
+            if node_type != NodeType::StructureType(StructureType::Tower(None)) {
+                node.neutral = NeutralGarrison::NONE;
+            }
+            node.node_type = node_type;
+            save(file, &map)
+        }
+        "set-neutral" => {
+            let file = arg(1, "file")?;
+            let mut map = load(file)?;
+            let raid_below = match args.iter().position(|arg| arg == "--raid-below") {
+                Some(index) => Some(parse_mana(arg(index + 1, "mana")?)?),
+                None => None,
+            };
+            // Optional positional arguments end where the flags begin
+            let flags_start = args
+                .iter()
+                .position(|arg| arg.starts_with("--"))
+                .unwrap_or(args.len());
+            let positional = |index: usize| args.get(index).filter(|_| index < flags_start);
+            let garrison = parse_mana(arg(3, "garrison")?)?;
+            let neutral = NeutralGarrison {
+                garrison,
+                regen_rate: optional(positional(4), 0)?,
+                regen_cap: optional(positional(5), garrison)?,
+                raid_below,
+            };
+            let node = find_node(&mut map, parse_id(arg(2, "id")?)?)?;
+            if node.node_type != NodeType::StructureType(StructureType::Tower(None)) {
+                bail!("Node {} is not a neutral tower", node.id);
+            }
+            node.neutral = neutral;
+            save(file, &map)
+        }
+        "validate" => {
+            let map = Map::from_file_unchecked(load(arg(1, "file")?)?);
+            match map.validate() {
+                Ok(()) => {
+                    println!("✅ Map '{}' is valid", map.name);
+                    Ok(())
+                }
+                Err(errors) => {
+                    for error in &errors {
+                        println!("  - {}", error);
+                    }
+                    bail!("Map '{}' has {} problem(s)", map.name, errors.len())
+                }
+            }
+        }
+        "analyze" => {
+            let map = Map::from_file_unchecked(load(arg(1, "file")?)?);
+            let report = map.analyze_fairness();
+            println!("{}", report);
+            if args.iter().any(|arg| arg == "--strict") && !report.is_fair() {
+                bail!("Map '{}' is not fair", map.name);
+            }
+            Ok(())
+        }
+        "generate" => {
+            let defaults = MapGenParams::default();
+            let params = MapGenParams {
+                seed: arg(2, "seed")?.parse().context("Invalid seed")?,
+                teams: optional(args.get(3), defaults.teams)?,
+                nodes_per_team: optional(args.get(4), defaults.nodes_per_team)?,
+                density: optional(args.get(5), defaults.density)?,
+                ..defaults
+            };
+            let map = generate_map(&params)?;
+            save(arg(1, "file")?, &map.to_file())
+        }
+        "export-example" => save(arg(1, "file")?, &Map::from_const(&EXAMPLE_MAP).to_file()),
+        "to-const" => {
+            print!("{}", to_const(&load(arg(1, "file")?)?));
+            Ok(())
+        }
+        "draw" => {
+            let map = Map::from_file_unchecked(load(arg(1, "file")?)?);
+            if args.iter().any(|arg| arg == "--svg") {
+                print!("{}", draw::svg(&map));
+            } else {
+                print!("{}", draw::ascii(&map));
+            }
+            Ok(())
+        }
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => bail!("Unknown command '{}'\n\n{}", other, USAGE),
+    }
+}
+
+fn load(path: impl AsRef<Path>) -> anyhow::Result<MapFile> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    ron::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Writes the map after bringing `edges` and `connected_to` in line.
+fn save(path: impl AsRef<Path>, map: &MapFile) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let map = Map::from_file_unchecked(map.clone()).to_file();
+    let text = ron::ser::to_string_pretty(&map, PrettyConfig::default().struct_names(true))?;
+    std::fs::write(path, text + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("💾 Saved {}", path.display());
+    Ok(())
+}
+
+fn find_node(map: &mut MapFile, id: NodeId) -> anyhow::Result<&mut MapNode> {
+    map.nodes
+        .iter_mut()
+        .find(|node| node.id == id)
+        .ok_or_else(|| anyhow!("Node {} does not exist", id))
+}
+
+fn parse_id(value: &str) -> anyhow::Result<NodeId> {
+    value
+        .parse()
+        .with_context(|| format!("Invalid node id '{}'", value))
+}
+
+fn parse_f32(value: &str) -> anyhow::Result<f32> {
+    value
+        .parse()
+        .with_context(|| format!("Invalid number '{}'", value))
+}
+
+fn parse_mana(value: &str) -> anyhow::Result<u8> {
+    value
+        .parse()
+        .with_context(|| format!("Invalid mana amount '{}'", value))
+}
+
+fn optional<T: std::str::FromStr>(value: Option<&String>, default: T) -> anyhow::Result<T> {
+    match value {
+        Some(value) => value
+            .parse()
+            .map_err(|_| anyhow!("Invalid value '{}'", value)),
+        None => Ok(default),
+    }
+}
+
+fn parse_terrain(value: &str) -> anyhow::Result<Terrain> {
+    Ok(match value {
+        "road" => Terrain::Road,
+        "swamp" => Terrain::Swamp,
+        "bridge" => Terrain::Bridge,
+        _ => bail!("Unknown terrain '{}'", value),
+    })
+}
+
+fn parse_node_type(value: &str) -> anyhow::Result<NodeType> {
+    let team = |team: &str| {
+        team.parse()
+            .with_context(|| format!("Invalid team '{}'", team))
+    };
+    Ok(match value.split_once(':') {
+        None if value == "waypoint" => NodeType::Waypoint,
+        None if value == "tower" => NodeType::StructureType(StructureType::Tower(None)),
+        Some(("tower", owner)) => NodeType::StructureType(StructureType::Tower(Some(team(owner)?))),
+        Some(("base", owner)) => NodeType::StructureType(StructureType::BaseTower(team(owner)?)),
+        _ => bail!("Unknown node type '{}'", value),
+    })
+}
+
+/// Renders the map in the form used by `EXAMPLE_MAP`, ready to paste into `map.rs`.
+fn to_const(map: &MapFile) -> String {
+    let const_name: String = map
+        .name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let mut nodes: Vec<&MapNode> = map.nodes.iter().collect();
+    nodes.sort_by_key(|node| node.id);
+
+    let mut out = format!(
+        "pub const {}: MapData = MapData {{\n    name: {:?},\n    nodes: &[\n",
+        const_name, map.name
+    );
+    for node in nodes {
+        let node_type = match &node.node_type {
+            NodeType::Waypoint => "NodeType::Waypoint".to_string(),
+            NodeType::StructureType(structure) => {
+                format!("NodeType::StructureType(StructureType::{:?})", structure)
+            }
+        };
+        let neutral = if node.neutral.is_none() {
+            "NeutralGarrison::NONE".to_string()
+        } else {
+            format!(
+                "NeutralGarrison {{\n                garrison: {},\n                regen_rate: {},\n                regen_cap: {},\n                raid_below: {:?},\n            }}",
+                node.neutral.garrison,
+                node.neutral.regen_rate,
+                node.neutral.regen_cap,
+                node.neutral.raid_below
+            )
+        };
+        out += &format!(
+            "        MapNodeData {{\n            id: {},\n            connected_to: &{:?},\n            position: Vec2::new({:?}, {:?}),\n            node_type: {},\n            neutral: {},\n        }},\n",
+            node.id, node.connected_to, node.position.x, node.position.y, node_type, neutral
+        );
+    }
+    out += "    ],\n    edges: &[\n";
+    for edge in &map.edges {
+        out += &format!(
+            "        MapEdge {{\n            from: {},\n            to: {},\n            terrain: Terrain::{:?},\n            length: {:?},\n            one_way: {},\n        }},\n",
+            edge.from, edge.to, edge.terrain, edge.length, edge.one_way
+        );
+    }
+    out += "    ],\n};\n";
+    out
+}
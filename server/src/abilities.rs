@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use shared::gameplay::{
+    abilities::{Ability, AbilityCooldowns, Frozen, ManaSurge, Shielded},
+    balance::Balance,
+    structures::Tower,
+    teams::Team,
+};
+use shared::protocol::{CastAbility, GameplayEvent, RejectionReason};
+
+use crate::command_validation::{Accepted, CommandSet, Rejected};
+
+/// Plugin to cast validated team abilities
+pub struct AbilityCastPlugin;
+
+impl Plugin for AbilityCastPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, handle_cast_ability.in_set(CommandSet::Apply));
+    }
+}
+
+fn handle_cast_ability(
+    mut commands: Commands,
+    mut accepted: EventReader<Accepted<CastAbility>>,
+    mut q_towers: Query<(Entity, &mut Tower)>,
+    mut q_teams: Query<(Entity, &Team, &mut AbilityCooldowns)>,
+    balance: Res<Balance>,
+    mut events: EventWriter<GameplayEvent>,
+    mut rejected: EventWriter<Rejected>,
+) {
+    for accepted in accepted.read() {
+        let Accepted {
+            player, command, ..
+        } = accepted;
+        let Some((team_entity, _, mut cooldowns)) = q_teams
+            .iter_mut()
+            .find(|(_, team, _)| team.id == player.team)
+        else {
+            rejected.write(accepted.reject(RejectionReason::NoTeam));
+            continue;
+        };
+        let cost = balance.ability(command.ability);
+
+        // Surge lives on the team, freeze and shield on the target tower
+        let holder = match command.ability {
+            Ability::ManaSurge => Some(team_entity),
+            Ability::Freeze | Ability::Shield => q_towers
+                .iter()
+                .find(|(_, tower)| Some(tower.node_id) == command.target)
+                .map(|(entity, _)| entity),
+        };
+        let Some(holder) = holder else {
+            rejected.write(accepted.reject(RejectionReason::InvalidTarget {
+                ability: command.ability,
+                target: command.target,
+            }));
+            continue;
+        };
+
+        let Some((_, mut source)) = q_towers
+            .iter_mut()
+            .find(|(_, tower)| tower.node_id == command.source)
+        else {
+            rejected.write(accepted.reject(RejectionReason::NoTower(command.source)));
+            continue;
+        };
+        if let Err(rejection) = accepted.pay(&mut source, cost.mana) {
+            rejected.write(rejection);
+            continue;
+        }
+
+        let remaining = cost.duration;
+        match command.ability {
+            Ability::Freeze => commands.entity(holder).insert(Frozen { remaining }),
+            Ability::ManaSurge => commands.entity(holder).insert(ManaSurge { remaining }),
+            Ability::Shield => commands.entity(holder).insert(Shielded { remaining }),
+        };
+        cooldowns.start(command.ability, cost.cooldown);
+        events.write(GameplayEvent::AbilityCast {
+            team: player.team,
+            ability: command.ability,
+            target: command.target,
+        });
+        info!(
+            "✨ Team {} cast {:?} from tower {} on {:?}",
+            player.team, command.ability, command.source, command.target
+        );
+    }
+}
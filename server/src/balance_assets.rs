@@ -0,0 +1,82 @@
+use bevy::asset::AssetLoadFailedEvent;
+use bevy::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use lightyear::prelude::*;
+use shared::gameplay::{balance::Balance, map::format_validation_errors};
+use shared::protocol::{BalanceUpdate, CommandChannel};
+
+use crate::ClientPlayer;
+
+const BALANCE_ASSET_PATH: &str = "game.balance.ron";
+
+/// Plugin to load `Balance` from a RON asset and push hot-reloaded values to clients
+pub struct BalanceAssetPlugin;
+
+impl Plugin for BalanceAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<Balance>::new(&["balance.ron"]));
+        app.add_systems(Startup, load_balance);
+        app.add_systems(Update, (apply_loaded_balance, report_failed_balance));
+    }
+}
+
+/// Present once the balance file has been loaded, or failed to load and the
+/// defaults stay in effect.
+#[derive(Resource, Debug)]
+pub struct BalanceReady;
+
+#[derive(Resource)]
+struct BalanceHandle(Handle<Balance>);
+
+fn load_balance(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BalanceHandle(asset_server.load(BALANCE_ASSET_PATH)));
+}
+
+fn apply_loaded_balance(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Balance>>,
+    handle: Res<BalanceHandle>,
+    assets: Res<Assets<Balance>>,
+    mut balance: ResMut<Balance>,
+    mut q_senders: Query<&mut MessageSender<BalanceUpdate>, With<ClientPlayer>>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+        let Some(loaded) = assets.get(&handle.0) else {
+            continue;
+        };
+        commands.insert_resource(BalanceReady);
+        if let Err(errors) = loaded.validate() {
+            eprintln!(
+                "❌ Balance in {} is invalid, keeping the previous values:\n{}",
+                BALANCE_ASSET_PATH,
+                format_validation_errors(&errors)
+            );
+            continue;
+        }
+
+        println!("⚖️ Loaded balance from {}", BALANCE_ASSET_PATH);
+        *balance = loaded.clone();
+
+        // Verified clients get the new numbers right away; clients joining
+        // later receive them at match start.
+        for mut sender in q_senders.iter_mut() {
+            sender.send::<CommandChannel>(BalanceUpdate(balance.clone()));
+        }
+    }
+}
+
+fn report_failed_balance(
+    mut commands: Commands,
+    mut events: EventReader<AssetLoadFailedEvent<Balance>>,
+) {
+    for event in events.read() {
+        eprintln!(
+            "⚠️ Failed to load balance {}: {}, keeping the previous values",
+            event.path, event.error
+        );
+        commands.insert_resource(BalanceReady);
+    }
+}
@@ -0,0 +1,433 @@
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use shared::gameplay::{
+    abilities::{Ability, AbilityCooldowns},
+    balance::Balance,
+    map::{CurrentMap, Map, NodeId},
+    routing::RouteTable,
+    state::{CurrentGameState, GameState as SimulationState, SimulationSet},
+    structures::{TeamId, Tower, TowerStats, TowerUpgrading},
+    teams::{Team, TeamRegistry},
+};
+use shared::protocol::{
+    CastAbility, CommandChannel, CommandRejected, MatchState, RejectionReason, SendTroops,
+    UpgradeTower,
+};
+use std::collections::HashMap;
+
+use crate::ClientPlayer;
+
+/// Plugin that checks every gameplay command from clients before it is applied.
+/// Accepted commands are forwarded as `Accepted` events, rejected ones are
+/// answered with `CommandRejected`.
+pub struct CommandValidationPlugin;
+
+impl Plugin for CommandValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Accepted<SendTroops>>()
+            .add_event::<Accepted<UpgradeTower>>()
+            .add_event::<Accepted<CastAbility>>()
+            .add_event::<Rejected>()
+            .configure_sets(
+                Update,
+                (CommandSet::Validate, CommandSet::Apply, CommandSet::Reply)
+                    .chain()
+                    .before(SimulationSet),
+            )
+            .add_systems(Update, validate_commands.in_set(CommandSet::Validate))
+            .add_systems(Update, send_rejections.in_set(CommandSet::Reply));
+    }
+}
+
+/// Client commands are validated and applied before the simulation runs, so
+/// nothing changes a tower between the check and the handler.
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CommandSet {
+    Validate,
+    /// Handlers of `Accepted` commands go here
+    Apply,
+    Reply,
+}
+
+/// A command that passed validation and can be applied as is.
+#[derive(Event, Clone, Debug)]
+pub struct Accepted<C: Send + Sync + 'static> {
+    pub client: Entity,
+    pub player: ClientPlayer,
+    pub command: C,
+}
+
+impl<C: Send + Sync + 'static> Accepted<C> {
+    pub fn reject(&self, reason: RejectionReason) -> Rejected {
+        Rejected {
+            client: self.client,
+            player: self.player,
+            reason,
+        }
+    }
+
+    /// Takes `mana` from the sender's `tower`. Validation checked both against
+    /// a snapshot, so an earlier command this frame may have changed the tower.
+    pub fn pay(&self, tower: &mut Tower, mana: u8) -> Result<(), Rejected> {
+        if tower.owner != Some(self.player.team) {
+            return Err(self.reject(RejectionReason::NotYourTower(tower.node_id)));
+        }
+        let Some(remaining) = tower.mana.checked_sub(mana) else {
+            return Err(self.reject(RejectionReason::NotEnoughMana {
+                node: tower.node_id,
+                required: mana,
+                available: tower.mana,
+            }));
+        };
+        tower.mana = remaining;
+        Ok(())
+    }
+}
+
+/// A command that failed validation, or could not be applied after all.
+/// Answered with `CommandRejected` at the end of `CommandSet`.
+#[derive(Event, Clone, Debug)]
+pub struct Rejected {
+    pub client: Entity,
+    pub player: ClientPlayer,
+    pub reason: RejectionReason,
+}
+
+struct TowerView {
+    owner: Option<TeamId>,
+    mana: u8,
+    level: u8,
+    upgrading: bool,
+}
+
+/// Snapshot of everything a command is checked against. Accepted commands
+/// update the snapshot, so several commands in one frame cannot spend the
+/// same mana twice.
+struct Validator<'a> {
+    running: bool,
+    map: Option<&'a Map>,
+    routes: &'a RouteTable,
+    teams: &'a TeamRegistry,
+    balance: &'a Balance,
+    towers: HashMap<NodeId, TowerView>,
+    cooldowns: HashMap<TeamId, AbilityCooldowns>,
+}
+
+impl Validator<'_> {
+    fn tower(&self, node: NodeId) -> Result<&TowerView, RejectionReason> {
+        if !self.map.is_some_and(|map| map.get_node(node).is_some()) {
+            return Err(RejectionReason::UnknownNode(node));
+        }
+        self.towers.get(&node).ok_or(RejectionReason::NoTower(node))
+    }
+
+    /// The sender's own tower with at least `mana` in it
+    fn source(
+        &self,
+        player: &ClientPlayer,
+        node: NodeId,
+        mana: u8,
+    ) -> Result<&TowerView, RejectionReason> {
+        let tower = self.tower(node)?;
+        if tower.owner != Some(player.team) {
+            return Err(RejectionReason::NotYourTower(node));
+        }
+        if tower.mana < mana {
+            return Err(RejectionReason::NotEnoughMana {
+                node,
+                required: mana,
+                available: tower.mana,
+            });
+        }
+        Ok(tower)
+    }
+
+    fn spend(&mut self, node: NodeId, mana: u8) {
+        if let Some(tower) = self.towers.get_mut(&node) {
+            tower.mana -= mana;
+        }
+    }
+
+    fn check_running(&self) -> Result<(), RejectionReason> {
+        if self.running {
+            Ok(())
+        } else {
+            Err(RejectionReason::MatchNotRunning)
+        }
+    }
+
+    fn send_troops(
+        &mut self,
+        player: &ClientPlayer,
+        request: &SendTroops,
+    ) -> Result<(), RejectionReason> {
+        self.check_running()?;
+        if request.amount == 0 {
+            return Err(RejectionReason::InvalidAmount);
+        }
+        let source = self.source(player, request.from, request.amount)?;
+        if source.upgrading {
+            return Err(RejectionReason::TowerUpgrading(request.from));
+        }
+        // Troops reaching a waypoint would be lost
+        self.tower(request.to)?;
+        if request.from == request.to || !self.routes.is_reachable(request.from, request.to) {
+            return Err(RejectionReason::Unreachable {
+                from: request.from,
+                to: request.to,
+            });
+        }
+
+        self.spend(request.from, request.amount);
+        Ok(())
+    }
+
+    fn upgrade_tower(
+        &mut self,
+        player: &ClientPlayer,
+        request: &UpgradeTower,
+    ) -> Result<(), RejectionReason> {
+        self.check_running()?;
+        let tower = self.source(player, request.node_id, 0)?;
+        if tower.upgrading {
+            return Err(RejectionReason::TowerUpgrading(request.node_id));
+        }
+        let cost = self
+            .balance
+            .upgrade_cost(tower.level)
+            .ok_or(RejectionReason::MaxLevel(request.node_id))?;
+        self.source(player, request.node_id, cost.mana)?;
+
+        self.spend(request.node_id, cost.mana);
+        if let Some(tower) = self.towers.get_mut(&request.node_id) {
+            tower.upgrading = true;
+        }
+        Ok(())
+    }
+
+    fn cast_ability(
+        &mut self,
+        player: &ClientPlayer,
+        request: &CastAbility,
+    ) -> Result<(), RejectionReason> {
+        self.check_running()?;
+        let cooldowns = self
+            .cooldowns
+            .get(&player.team)
+            .ok_or(RejectionReason::NoTeam)?;
+        if !cooldowns.is_ready(request.ability) {
+            return Err(RejectionReason::OnCooldown {
+                ability: request.ability,
+                remaining: cooldowns.remaining(request.ability),
+            });
+        }
+        let cost = self.balance.ability(request.ability);
+        self.source(player, request.source, cost.mana)?;
+
+        // Freeze hits an enemy or neutral tower, shield protects a friendly one
+        if request.ability != Ability::ManaSurge {
+            let invalid_target = RejectionReason::InvalidTarget {
+                ability: request.ability,
+                target: request.target,
+            };
+            let target = request.target.ok_or(invalid_target.clone())?;
+            let friendly = self.teams.is_friendly(player.team, self.tower(target)?.owner);
+            if friendly != (request.ability == Ability::Shield) {
+                return Err(invalid_target);
+            }
+        }
+
+        self.spend(request.source, cost.mana);
+        if let Some(cooldowns) = self.cooldowns.get_mut(&player.team) {
+            cooldowns.start(request.ability, cost.cooldown);
+        }
+        Ok(())
+    }
+}
+
+/// Commands are validated per kind: troop dispatches, then upgrades, then
+/// abilities. The order between kinds within a frame is not preserved.
+#[allow(clippy::too_many_arguments)]
+fn validate_commands(
+    mut q_clients: Query<(
+        Entity,
+        &ClientPlayer,
+        &mut MessageReceiver<SendTroops>,
+        &mut MessageReceiver<UpgradeTower>,
+        &mut MessageReceiver<CastAbility>,
+    )>,
+    q_state: Query<&CurrentGameState>,
+    q_map: Query<&CurrentMap>,
+    q_towers: Query<(&Tower, &TowerStats, Has<TowerUpgrading>)>,
+    q_teams: Query<(&Team, &AbilityCooldowns)>,
+    match_state: Res<State<MatchState>>,
+    route_table: Res<RouteTable>,
+    teams: Res<TeamRegistry>,
+    balance: Res<Balance>,
+    mut accepted_troops: EventWriter<Accepted<SendTroops>>,
+    mut accepted_upgrades: EventWriter<Accepted<UpgradeTower>>,
+    mut accepted_casts: EventWriter<Accepted<CastAbility>>,
+    mut rejected: EventWriter<Rejected>,
+) {
+    let in_progress = *match_state.get() == MatchState::InProgress;
+    let mut validator = Validator {
+        running: in_progress
+            && q_state
+                .single()
+                .is_ok_and(|state| state.0 == SimulationState::Running),
+        map: q_map.single().ok().map(|map| &map.0),
+        routes: &route_table,
+        teams: &teams,
+        balance: &balance,
+        towers: q_towers
+            .iter()
+            .map(|(tower, stats, upgrading)| {
+                let view = TowerView {
+                    owner: tower.owner,
+                    mana: tower.mana,
+                    level: stats.level(),
+                    upgrading,
+                };
+                (tower.node_id, view)
+            })
+            .collect(),
+        cooldowns: q_teams
+            .iter()
+            .map(|(team, cooldowns)| (team.id, cooldowns.clone()))
+            .collect(),
+    };
+
+    for (client, player, mut troops, mut upgrades, mut casts) in q_clients.iter_mut() {
+        let mut reject = |reason: RejectionReason| {
+            rejected.write(Rejected {
+                client,
+                player: *player,
+                reason,
+            });
+        };
+
+        for command in troops.receive() {
+            match validator.send_troops(player, &command) {
+                Ok(()) => {
+                    accepted_troops.write(Accepted {
+                        client,
+                        player: *player,
+                        command,
+                    });
+                }
+                Err(reason) => reject(reason),
+            }
+        }
+        for command in upgrades.receive() {
+            match validator.upgrade_tower(player, &command) {
+                Ok(()) => {
+                    accepted_upgrades.write(Accepted {
+                        client,
+                        player: *player,
+                        command,
+                    });
+                }
+                Err(reason) => reject(reason),
+            }
+        }
+        for command in casts.receive() {
+            match validator.cast_ability(player, &command) {
+                Ok(()) => {
+                    accepted_casts.write(Accepted {
+                        client,
+                        player: *player,
+                        command,
+                    });
+                }
+                Err(reason) => reject(reason),
+            }
+        }
+    }
+}
+
+fn send_rejections(
+    mut rejected: EventReader<Rejected>,
+    mut q_senders: Query<&mut MessageSender<CommandRejected>>,
+) {
+    for Rejected {
+        client,
+        player,
+        reason,
+    } in rejected.read()
+    {
+        warn!(
+            "Rejected command from player {}: {}",
+            player.player_id, reason
+        );
+        if let Ok(mut sender) = q_senders.get_mut(*client) {
+            sender.send::<CommandChannel>(CommandRejected {
+                reason: reason.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::gameplay::map::EXAMPLE_MAP;
+
+    /// Validator over the example map, where team 1 holds tower 3 with
+    /// 10 mana and node 2 is a waypoint
+    fn validate_send(map: &Map, request: SendTroops) -> Result<(), RejectionReason> {
+        let routes = RouteTable::build(map);
+        let teams = TeamRegistry::default();
+        let balance = Balance::default();
+        let towers = [(1, None, 5), (3, Some(1), 10), (4, Some(2), 10)]
+            .into_iter()
+            .map(|(node, owner, mana)| {
+                let view = TowerView {
+                    owner,
+                    mana,
+                    level: 1,
+                    upgrading: false,
+                };
+                (node, view)
+            })
+            .collect();
+        let mut validator = Validator {
+            running: true,
+            map: Some(map),
+            routes: &routes,
+            teams: &teams,
+            balance: &balance,
+            towers,
+            cooldowns: HashMap::new(),
+        };
+        let player = ClientPlayer {
+            player_id: 1,
+            team: 1,
+        };
+        validator.send_troops(&player, &request)
+    }
+
+    #[test]
+    fn troops_can_be_sent_to_a_tower() {
+        let map = Map::from_const(&EXAMPLE_MAP);
+        let request = SendTroops {
+            from: 3,
+            to: 4,
+            amount: 5,
+        };
+        assert_eq!(validate_send(&map, request), Ok(()));
+    }
+
+    #[test]
+    fn troops_cannot_be_sent_to_a_waypoint() {
+        let map = Map::from_const(&EXAMPLE_MAP);
+        let request = SendTroops {
+            from: 3,
+            to: 2,
+            amount: 5,
+        };
+        assert_eq!(
+            validate_send(&map, request),
+            Err(RejectionReason::NoTower(2))
+        );
+    }
+}
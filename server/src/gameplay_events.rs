@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use shared::gameplay::combat::{TowerCaptured, TowerRaided, TroopGroupsClashed};
+use shared::protocol::{CommandChannel, GameplayEvent};
+
+use crate::ClientPlayer;
+
+/// Plugin to broadcast what happens in the match to every verified client
+pub struct GameplayEventPlugin;
+
+impl Plugin for GameplayEventPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameplayEvent>().add_systems(
+            PostUpdate,
+            (collect_combat_events, broadcast_gameplay_events).chain(),
+        );
+    }
+}
+
+fn collect_combat_events(
+    mut captured: EventReader<TowerCaptured>,
+    mut raided: EventReader<TowerRaided>,
+    mut clashed: EventReader<TroopGroupsClashed>,
+    mut events: EventWriter<GameplayEvent>,
+) {
+    for capture in captured.read() {
+        events.write(GameplayEvent::TowerCaptured {
+            node_id: capture.node_id,
+            previous_owner: capture.previous_owner,
+            new_owner: capture.new_owner,
+        });
+    }
+    for raid in raided.read() {
+        events.write(GameplayEvent::TowerRaided {
+            raider: raid.raider,
+            node_id: raid.node_id,
+            previous_owner: raid.previous_owner,
+            captured: raid.captured,
+        });
+    }
+    for clash in clashed.read() {
+        events.write(GameplayEvent::TroopsClashed {
+            edge: clash.edge,
+            teams: clash.teams,
+            losses: clash.losses,
+        });
+    }
+}
+
+fn broadcast_gameplay_events(
+    mut events: EventReader<GameplayEvent>,
+    mut q_senders: Query<&mut MessageSender<GameplayEvent>, With<ClientPlayer>>,
+) {
+    for event in events.read() {
+        for mut sender in q_senders.iter_mut() {
+            sender.send::<CommandChannel>(event.clone());
+        }
+    }
+}
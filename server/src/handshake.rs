@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use shared::protocol::{ClientHello, CommandChannel, ProtocolManifest, VersionMismatch};
+
+/// Seconds a client has to say hello before it is dropped
+const HELLO_TIMEOUT: f32 = 10.0;
+/// Seconds between telling a client about a mismatch and dropping it, so the
+/// `VersionMismatch` reaches it first
+const MISMATCH_GRACE: f32 = 1.0;
+
+/// Plugin to check every client's protocol version and drop the ones that do not match
+pub struct HandshakePlugin;
+
+impl Plugin for HandshakePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(await_hello)
+            .add_systems(Startup, log_protocol_version)
+            .add_systems(Update, (check_client_hello, drop_unverified_clients).chain());
+    }
+}
+
+/// A connected client that has not proven its protocol version yet. Once the
+/// timer runs out the client is disconnected.
+#[derive(Component, Debug)]
+struct Unverified(Timer);
+
+/// Triggered on a client entity once its protocol version matched. Only then
+/// is the client registered as a player.
+#[derive(Event, Debug)]
+pub struct ClientVerified;
+
+fn log_protocol_version(manifest: Res<ProtocolManifest>) {
+    println!("📜 Protocol version {:016x}", manifest.version());
+}
+
+fn await_hello(trigger: Trigger<OnAdd, Connected>, mut commands: Commands) {
+    commands
+        .entity(trigger.target())
+        .insert(Unverified(Timer::from_seconds(HELLO_TIMEOUT, TimerMode::Once)));
+}
+
+fn check_client_hello(
+    mut commands: Commands,
+    mut q_clients: Query<(
+        Entity,
+        &mut Unverified,
+        &mut MessageReceiver<ClientHello>,
+        &mut MessageSender<VersionMismatch>,
+    )>,
+    manifest: Res<ProtocolManifest>,
+) {
+    let server = manifest.version();
+    for (entity, mut unverified, mut receiver, mut sender) in q_clients.iter_mut() {
+        for hello in receiver.receive() {
+            if hello.version == server {
+                info!("Client {:?} speaks protocol {:016x}", entity, server);
+                commands.entity(entity).remove::<Unverified>();
+                commands.trigger_targets(ClientVerified, entity);
+                break;
+            }
+
+            println!(
+                "🚫 Client {:?} has protocol version {:016x}, expected {:016x}",
+                entity, hello.version, server
+            );
+            sender.send::<CommandChannel>(VersionMismatch {
+                server,
+                client: hello.version,
+            });
+            unverified.0 = Timer::from_seconds(MISMATCH_GRACE, TimerMode::Once);
+        }
+    }
+}
+
+/// Also drops clients too old to send a `ClientHello` at all.
+fn drop_unverified_clients(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut q_clients: Query<(Entity, &mut Unverified)>,
+) {
+    for (entity, mut unverified) in q_clients.iter_mut() {
+        if unverified.0.tick(time.delta()).just_finished() {
+            println!("🔌 Disconnecting unverified client {:?}", entity);
+            commands.trigger_targets(Disconnect, entity);
+        }
+    }
+}
@@ -1,18 +1,48 @@
 use bevy::asset::AssetPlugin;
 use bevy::log::LogPlugin;
 use bevy::prelude::*;
-use bevy_common_assets::ron::RonAssetPlugin;
+use bevy::state::app::StatesPlugin;
 use lightyear::prelude::server::*;
 use lightyear::prelude::*;
 use serde::{Deserialize, Serialize};
+use shared::gameplay::{
+    balance::Balance, match_clock::MatchClock, speed::GameSpeed, state::SimulationAuthority,
+    structures::TeamId,
+};
+use shared::protocol::{
+    BalanceUpdate, CommandChannel, MatchStarted, MatchState, MatchStateChanged,
+    NETCODE_PROTOCOL_ID, PingChannel, PingMessage,
+};
 use shared::*;
+use std::collections::HashMap;
 use std::env;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-use std::sync::{Arc, Mutex};
 
+use crate::abilities::AbilityCastPlugin;
+use crate::balance_assets::{BalanceAssetPlugin, BalanceReady};
+use crate::command_validation::CommandValidationPlugin;
+use crate::gameplay_events::GameplayEventPlugin;
+use crate::handshake::{ClientVerified, HandshakePlugin};
+use crate::map_assets::{MapAssetPlugin, MapReady};
 use crate::map_init::MapInitPlugin;
-
+use crate::pause::PausePlugin;
+use crate::teams::TeamSetupPlugin;
+use crate::tower_upgrades::TowerUpgradePlugin;
+use crate::troop_dispatch::TroopDispatchPlugin;
+use crate::victory::VictoryPlugin;
+
+mod abilities;
+mod balance_assets;
+mod command_validation;
+mod gameplay_events;
+mod handshake;
+mod map_assets;
 mod map_init;
+mod pause;
+mod teams;
+mod tower_upgrades;
+mod troop_dispatch;
+mod victory;
 
 #[derive(Resource, Clone)]
 pub struct ServerConfig {
@@ -22,21 +52,24 @@ pub struct ServerConfig {
     pub server_port: u16,
     pub server_addr: SocketAddr,
     pub backend_url: String,
+    /// Map to play, as a file stem in `assets/maps`. `None` uses the built-in example map.
+    pub map_name: Option<String>,
+    /// Groups of allied teams, e.g. `[[1, 3], [2, 4]]` for a 2v2. Empty means free-for-all.
+    pub alliances: Vec<Vec<TeamId>>,
+    /// Simulation speed multiplier for practice matches and tests
+    pub game_speed: GameSpeed,
 }
 
-#[derive(Debug, Clone, PartialEq, States, Hash, Eq)]
-pub enum GameState {
-    WaitingForPlayers,
-    MatchStarting,
-    InProgress,
-    Completed,
+/// Player identity and team of a connected client, stored on its connection entity
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ClientPlayer {
+    pub player_id: u32,
+    pub team: TeamId,
 }
 
-#[derive(Resource)]
-pub struct GameStateManager {
-    pub state: Arc<Mutex<GameState>>,
-    pub connected_players: Arc<Mutex<Vec<u32>>>,
-}
+/// Ids of the expected players that have connected, by connection entity
+#[derive(Resource, Debug, Default)]
+pub struct ConnectedPlayers(pub HashMap<Entity, u32>);
 
 #[derive(Serialize, Deserialize)]
 struct ServerReadyWebhook {
@@ -47,6 +80,8 @@ struct ServerReadyWebhook {
 struct MatchCompleteWebhook {
     match_id: u32,
     winner: Option<u32>,
+    /// Every player of the winning alliance
+    winners: Vec<u32>,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -74,6 +109,9 @@ fn main() -> anyhow::Result<()> {
         server_addr,
         backend_url: env::var("BACKEND_URL")
             .unwrap_or_else(|_| "http://host.docker.internal:3333".to_string()),
+        map_name: env::var("MAP_NAME").ok(),
+        alliances: parse_alliances(),
+        game_speed: parse_game_speed(),
     };
 
     println!("Match ID: {}", server_config.match_id);
@@ -84,12 +122,6 @@ fn main() -> anyhow::Result<()> {
     println!("Expected players: {:?}", server_config.expected_players);
     println!("Backend URL: {}", server_config.backend_url); */
 
-    // Initialize game state manager
-    let game_state_manager = GameStateManager {
-        state: Arc::new(Mutex::new(GameState::WaitingForPlayers)),
-        connected_players: Arc::new(Mutex::new(Vec::new())),
-    };
-
     // Notify backend that server is ready (spawn blocking task)
     // let config_clone = server_config.clone();
     // std::thread::spawn(move || {
@@ -125,17 +157,24 @@ fn main() -> anyhow::Result<()> {
     let server_config = ServerConfig {
         server_secret: "HelloWorld".to_string(),
         match_id: 1,
-        expected_players: vec![],
+        expected_players: parse_expected_players(),
         server_port,
         server_addr,
         backend_url: "".to_string(),
+        map_name: env::var("MAP_NAME").ok(),
+        alliances: parse_alliances(),
+        game_speed: parse_game_speed(),
     };
 
     app.insert_resource(server_config);
-    app.insert_resource(game_state_manager);
 
     app.add_plugins(MinimalPlugins);
-    app.add_plugins(AssetPlugin::default());
+    app.add_plugins(StatesPlugin);
+    app.add_plugins(AssetPlugin {
+        // Hot-reload balance and map files while the server runs
+        watch_for_changes_override: Some(true),
+        ..default()
+    });
     app.add_plugins(ServerPlugins {
         tick_duration: core::time::Duration::from_secs_f64(1.0 / FIXED_TIMESTEP_HZ),
     });
@@ -147,135 +186,208 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-// These structs are no longer needed since we use serde_json::json! macro
-// but keeping them for reference if needed later
+/// Reads `EXPECTED_PLAYERS` as a JSON array of player ids. Missing means the
+/// single player 1, which is the client id of the test clients.
+fn parse_expected_players() -> Vec<u32> {
+    env::var("EXPECTED_PLAYERS")
+        .map(|players| {
+            serde_json::from_str(&players)
+                .expect("EXPECTED_PLAYERS must be a JSON array of player ids, e.g. [1,2]")
+        })
+        .unwrap_or_else(|_| vec![1])
+}
+
+/// Reads `ALLIANCES` as a JSON array of team id arrays. Missing means free-for-all.
+fn parse_alliances() -> Vec<Vec<TeamId>> {
+    env::var("ALLIANCES")
+        .map(|alliances| {
+            serde_json::from_str(&alliances)
+                .expect("ALLIANCES must be a JSON array of team id arrays, e.g. [[1,3],[2,4]]")
+        })
+        .unwrap_or_default()
+}
+
+/// Reads `GAME_SPEED`, clamped to the supported range. Missing means normal speed.
+fn parse_game_speed() -> GameSpeed {
+    env::var("GAME_SPEED")
+        .map(|speed| GameSpeed::new(speed.parse().expect("GAME_SPEED must be a number")))
+        .unwrap_or_default()
+}
 
 pub struct ServerPlugin;
 
 impl Plugin for ServerPlugin {
     fn build(&self, app: &mut App) {
+        // The server alone runs the simulation, clients get the replicated results
+        app.init_resource::<SimulationAuthority>();
+        app.init_resource::<ConnectedPlayers>();
+        app.init_state::<MatchState>();
         app.add_systems(Startup, startup);
         app.add_systems(Update, start_server);
         app.add_observer(handle_new_client);
+        app.add_observer(register_player);
         app.add_observer(handle_client_disconnect);
         app.add_systems(
             Update,
             (
                 handle_ping_message,
-                check_all_players_connected,
-                game_state_manager,
+                announce_match_state,
+                // The match is set up from the map and balance, so both have to be in place
+                check_all_players_connected.run_if(
+                    in_state(MatchState::WaitingForPlayers)
+                        .and(resource_exists::<MapReady>)
+                        .and(resource_exists::<BalanceReady>),
+                ),
             ),
         );
-        app.add_plugins(MapInitPlugin);
+        app.add_systems(OnEnter(MatchState::MatchStarting), start_match);
+        app.add_plugins((
+            AbilityCastPlugin,
+            BalanceAssetPlugin,
+            CommandValidationPlugin,
+            GameplayEventPlugin,
+            HandshakePlugin,
+            MapAssetPlugin,
+            MapInitPlugin,
+            PausePlugin,
+            TeamSetupPlugin,
+            TroopDispatchPlugin,
+            TowerUpgradePlugin,
+            VictoryPlugin,
+        ));
     }
 }
 
-fn handle_new_client(
-    trigger: Trigger<OnAdd, Connected>,
+fn handle_new_client(trigger: Trigger<OnAdd, Connected>) {
+    println!("🔌 New client connected: {:?}", trigger.target());
+}
+
+/// Runs once the client's protocol version matched. The netcode client id is
+/// the player id. Clients that are not expected, or whose player is already
+/// connected, are disconnected.
+fn register_player(
+    trigger: Trigger<ClientVerified>,
     mut commands: Commands,
+    q_remote: Query<&RemoteId>,
     config: Res<ServerConfig>,
-    game_state: Res<GameStateManager>,
+    mut connected: ResMut<ConnectedPlayers>,
 ) {
     let client_id = trigger.target();
-    println!("🔌 New client connected: {:?}", client_id);
 
     // TODO: In a real implementation, validate the client's connection
-    // using SERVER_SECRET and expected player ID
-    // For now, we'll assume valid connections
-
-    commands.entity(client_id).insert((ReplicationSender::new(
-        SERVER_REPLICATION_INTERVAL,
-        SendUpdatesMode::SinceLastAck,
-        false,
-    ),));
-
-    // Add connected player to our tracking
-    let game_state_clone = game_state.connected_players.clone();
-    let expected_players = config.expected_players.clone();
-
-    if let Ok(mut connected) = game_state_clone.lock() {
-        let con_len = connected.len();
-        // For now, just track connection count - in real implementation
-        // we'd extract player ID from connection authentication
-        if con_len < expected_players.len() {
-            connected.push(expected_players[con_len]);
-            println!(
-                "👤 Player connected. Total: {}/{}",
-                connected.len(),
-                expected_players.len()
-            );
-        }
+    // using SERVER_SECRET as well
+
+    let player_id = match q_remote.get(client_id) {
+        Ok(RemoteId(PeerId::Netcode(id))) => u32::try_from(*id).ok(),
+        _ => None,
+    };
+    // Map teams are numbered from 1, in the order of the expected players
+    let slot = player_id.and_then(|player_id| {
+        let index = config.expected_players.iter().position(|id| *id == player_id)?;
+        Some((player_id, index as TeamId + 1))
+    });
+    let Some((player_id, team)) = slot else {
+        println!("🚫 Client {:?} is not an expected player", client_id);
+        commands.trigger_targets(Disconnect, client_id);
+        return;
+    };
+    if connected.0.values().any(|id| *id == player_id) {
+        println!("🚫 Player {} is already connected", player_id);
+        commands.trigger_targets(Disconnect, client_id);
+        return;
     }
+
+    commands.entity(client_id).insert((
+        ReplicationSender::new(
+            SERVER_REPLICATION_INTERVAL,
+            SendUpdatesMode::SinceLastAck,
+            false,
+        ),
+        ClientPlayer { player_id, team },
+    ));
+
+    connected.0.insert(client_id, player_id);
+    println!(
+        "👤 Player {} connected as team {}. Total: {}/{}",
+        player_id,
+        team,
+        connected.0.len(),
+        config.expected_players.len()
+    );
 }
 
 fn handle_client_disconnect(
     trigger: Trigger<OnRemove, Connected>,
-    game_state: Res<GameStateManager>,
+    mut connected: ResMut<ConnectedPlayers>,
 ) {
     let client_id = trigger.target();
     info!("🔌 Client disconnected: {:?}", client_id);
 
-    if let Ok(mut connected) = game_state.connected_players.lock() {
-        if !connected.is_empty() {
-            connected.pop();
-            println!("👤 Player disconnected. Remaining: {}", connected.len());
-        }
+    if let Some(player_id) = connected.0.remove(&client_id) {
+        println!(
+            "👤 Player {} disconnected. Remaining: {}",
+            player_id,
+            connected.0.len()
+        );
     }
 }
 
-// Game state management systems
+// Match lifecycle systems
 fn check_all_players_connected(
     config: Res<ServerConfig>,
-    game_state: Res<GameStateManager>,
-    mut sender: ServerMultiMessageSender,
-    server: Query<&Server>,
+    connected: Res<ConnectedPlayers>,
+    mut next_state: ResMut<NextState<MatchState>>,
 ) {
-    let expected_count = config.expected_players.len();
-
-    if let (Ok(current_state), Ok(connected)) =
-        (game_state.state.lock(), game_state.connected_players.lock())
-    {
-        if *current_state == GameState::WaitingForPlayers && connected.len() == expected_count {
-            drop(current_state);
-            drop(connected);
-
-            // Transition to MatchStarting
-            if let Ok(mut state) = game_state.state.lock() {
-                *state = GameState::MatchStarting;
-                println!("🎯 All players connected! Starting match...");
-
-                // TODO: Send MatchStart message via Lightyear to all connected clients
-            }
-        }
+    if connected.0.len() == config.expected_players.len() {
+        println!("🎯 All players connected! Starting match...");
+        next_state.set(MatchState::MatchStarting);
+    }
+}
+
+/// Tells every player its team, sends the balance and starts the match clock.
+/// The map's structures are spawned by `MapInitPlugin` on the same transition.
+fn start_match(
+    mut commands: Commands,
+    balance: Res<Balance>,
+    config: Res<ServerConfig>,
+    mut q_players: Query<(
+        &ClientPlayer,
+        &mut MessageSender<MatchStarted>,
+        &mut MessageSender<BalanceUpdate>,
+    )>,
+    mut next_state: ResMut<NextState<MatchState>>,
+) {
+    println!("📢 Broadcasting MatchStarted to all players");
+    for (player, mut match_started, mut balance_update) in q_players.iter_mut() {
+        match_started.send::<CommandChannel>(MatchStarted {
+            match_id: config.match_id,
+            team: player.team,
+        });
+        balance_update.send::<CommandChannel>(BalanceUpdate(balance.clone()));
     }
+
+    commands.spawn((
+        MatchClock::new(balance.match_duration, balance.sudden_death_duration),
+        Replicate::to_clients(NetworkTarget::All),
+    ));
+
+    println!("🎮 Match is now in progress!");
+    next_state.set(MatchState::InProgress);
 }
 
-fn game_state_manager(
-    game_state: Res<GameStateManager>,
-    mut sender: ServerMultiMessageSender,
-    server: Query<&Server>,
+/// Logs every lifecycle transition and sends it to the verified clients
+fn announce_match_state(
+    mut transitions: EventReader<StateTransitionEvent<MatchState>>,
+    mut q_senders: Query<&mut MessageSender<MatchStateChanged>, With<ClientPlayer>>,
 ) {
-    if let Ok(current_state) = game_state.state.lock() {
-        match *current_state {
-            GameState::MatchStarting => {
-                // Send MatchStart message to all players
-                println!("📢 Broadcasting MatchStart to all players");
-                // TODO: Implement actual MatchStart message sending
-
-                // Transition to InProgress
-                drop(current_state);
-                if let Ok(mut state) = game_state.state.lock() {
-                    *state = GameState::InProgress;
-                    println!("🎮 Match is now in progress!");
-                }
-            }
-            GameState::InProgress => {
-                // Game logic runs here - handled by other systems
-            }
-            GameState::Completed => {
-                // Match completed - handled by other systems
-            }
-            _ => {}
+    for transition in transitions.read() {
+        let Some(state) = transition.entered else {
+            continue;
+        };
+        println!("🔁 Match state: {:?}", state);
+
+        for mut sender in q_senders.iter_mut() {
+            sender.send::<CommandChannel>(MatchStateChanged { state });
         }
     }
 }
@@ -286,7 +398,7 @@ fn startup(mut commands: Commands, config: Res<ServerConfig>) {
         Name::from("GameServer"),
         Server::default(), // ← Add Server marker component
         NetcodeServer::new(NetcodeConfig {
-            protocol_id: 15, // ← Match client protocol
+            protocol_id: NETCODE_PROTOCOL_ID,
             ..Default::default()
         }),
         LocalAddr(config.server_addr),
@@ -305,24 +417,19 @@ fn start_server(
     }
 }
 
+/// Answers pings from verified clients to the client that sent them
 fn handle_ping_message(
-    mut receiver: Query<&mut MessageReceiver<PingMessage>>,
-    mut sender: ServerMultiMessageSender,
-    server: Query<&Server>,
+    mut q_clients: Query<
+        (&mut MessageReceiver<PingMessage>, &mut MessageSender<PingMessage>),
+        With<ClientPlayer>,
+    >,
 ) {
-    if let Ok(server) = server.single() {
-        for mut receiver in receiver.iter_mut() {
-            for message in receiver.receive() {
-                info!("Received ping: {:?}", message);
-                println!("Received ping: {:?}", message);
-
-                let response = PingMessage(format!("Pong! Got: {}", message.0));
-                if let Err(e) =
-                    sender.send::<_, GameNetworkChannel>(&response, server, &NetworkTarget::All)
-                {
-                    error!("Failed to send response: {:?}", e);
-                }
-            }
+    for (mut receiver, mut sender) in q_clients.iter_mut() {
+        for message in receiver.receive() {
+            info!("Received ping: {:?}", message);
+            println!("Received ping: {:?}", message);
+
+            sender.send::<PingChannel>(PingMessage(format!("Pong! Got: {}", message.0)));
         }
     }
 }
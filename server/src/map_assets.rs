@@ -0,0 +1,194 @@
+use bevy::asset::AssetLoadFailedEvent;
+use bevy::prelude::*;
+use bevy_common_assets::ron::RonAssetPlugin;
+use lightyear::prelude::*;
+use shared::gameplay::map::{CurrentMap, EXAMPLE_MAP, Map, MapFile, format_validation_errors};
+use shared::protocol::MatchState;
+use std::path::Path;
+
+use crate::ServerConfig;
+
+/// Directory below `assets/` that holds the `*.map.ron` files
+const MAPS_DIR: &str = "maps";
+const MAP_EXTENSION: &str = "map.ron";
+
+/// Plugin to load the match map from disk, or use `EXAMPLE_MAP` when none is configured
+pub struct MapAssetPlugin;
+
+impl Plugin for MapAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<MapFile>::new(&[MAP_EXTENSION]));
+        app.insert_resource(MapRegistry::scan(Path::new("assets").join(MAPS_DIR)));
+        app.add_systems(Startup, load_configured_map);
+        app.add_systems(Update, (apply_loaded_map, report_failed_map));
+    }
+}
+
+/// Names of the maps available on disk, i.e. the file stems of `assets/maps/*.map.ron`
+#[derive(Resource, Debug, Default)]
+pub struct MapRegistry {
+    pub maps: Vec<String>,
+}
+
+impl MapRegistry {
+    fn scan(dir: impl AsRef<Path>) -> Self {
+        let suffix = format!(".{}", MAP_EXTENSION);
+        let mut maps: Vec<String> = std::fs::read_dir(dir.as_ref())
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_suffix(&suffix))
+                    .map(str::to_string)
+            })
+            .collect();
+        maps.sort();
+        Self { maps }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.maps.iter().any(|map| map == name)
+    }
+
+    pub fn asset_path(name: &str) -> String {
+        format!("{}/{}.{}", MAPS_DIR, name, MAP_EXTENSION)
+    }
+}
+
+/// Present once the map for this match is in `CurrentMap`, i.e. the configured
+/// map has been applied or no map is configured.
+#[derive(Resource, Debug)]
+pub struct MapReady;
+
+#[derive(Resource)]
+struct MapHandle {
+    handle: Handle<MapFile>,
+    /// Set once the file has been applied. Later loads are hot reloads.
+    applied: bool,
+}
+
+/// Spawns the replicated `CurrentMap` with the example map, which the
+/// configured map replaces once it has loaded. An unknown map stops the server.
+fn load_configured_map(
+    mut commands: Commands,
+    config: Res<ServerConfig>,
+    registry: Res<MapRegistry>,
+    asset_server: Res<AssetServer>,
+    mut exit: EventWriter<AppExit>,
+) {
+    commands.spawn((
+        CurrentMap(Map::from_const(&EXAMPLE_MAP)),
+        Replicate::to_clients(NetworkTarget::All),
+    ));
+    println!("🗺️ Available maps: {:?}", registry.maps);
+
+    let Some(map_name) = &config.map_name else {
+        println!("No map configured, using the built-in example map");
+        commands.insert_resource(MapReady);
+        return;
+    };
+
+    if !registry.contains(map_name) {
+        eprintln!(
+            "❌ Map '{}' not found in assets/{} (available: {:?})",
+            map_name, MAPS_DIR, registry.maps
+        );
+        exit.write(AppExit::error());
+        return;
+    }
+
+    commands.insert_resource(MapHandle {
+        handle: asset_server.load(MapRegistry::asset_path(map_name)),
+        applied: false,
+    });
+}
+
+/// Hot reloads are ignored while a match is being played, since the towers
+/// on the field belong to the current map. An invalid map stops the server
+/// on the first load; an invalid reload keeps the current map.
+fn apply_loaded_map(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<MapFile>>,
+    handle: Option<ResMut<MapHandle>>,
+    assets: Res<Assets<MapFile>>,
+    match_state: Res<State<MatchState>>,
+    mut q_map: Query<&mut CurrentMap>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(mut handle) = handle else {
+        return;
+    };
+
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.handle) && !event.is_modified(&handle.handle)
+        {
+            continue;
+        }
+        let Some(map_file) = assets.get(&handle.handle) else {
+            continue;
+        };
+
+        let in_match = matches!(
+            match_state.get(),
+            MatchState::MatchStarting | MatchState::InProgress
+        );
+        if handle.applied && in_match {
+            println!(
+                "⏸️ Map '{}' changed on disk, ignoring it during the match",
+                map_file.name
+            );
+            continue;
+        }
+
+        match Map::from_file(map_file.clone()) {
+            Ok(map) => {
+                println!("🗺️ Loaded map '{}'", map.name);
+                println!("{}", map.analyze_fairness());
+                if let Ok(mut current) = q_map.single_mut() {
+                    current.0 = map;
+                }
+                handle.applied = true;
+                commands.insert_resource(MapReady);
+            }
+            Err(errors) if handle.applied => {
+                eprintln!(
+                    "⚠️ Reloaded map '{}' is invalid, keeping the current map:\n{}",
+                    map_file.name,
+                    format_validation_errors(&errors)
+                );
+            }
+            Err(errors) => {
+                // Refuse to run a match on a broken map
+                eprintln!(
+                    "❌ Map '{}' is invalid:\n{}",
+                    map_file.name,
+                    format_validation_errors(&errors)
+                );
+                exit.write(AppExit::error());
+            }
+        }
+    }
+}
+
+/// A map that cannot be read stops the server on the first load, like an
+/// invalid one; a failed reload keeps the current map.
+fn report_failed_map(
+    mut events: EventReader<AssetLoadFailedEvent<MapFile>>,
+    handle: Option<Res<MapHandle>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for event in events.read() {
+        if handle.as_ref().is_some_and(|handle| handle.applied) {
+            eprintln!(
+                "⚠️ Failed to reload map {}: {}, keeping the current map",
+                event.path, event.error
+            );
+            continue;
+        }
+        eprintln!("❌ Failed to load map {}: {}", event.path, event.error);
+        exit.write(AppExit::error());
+    }
+}
@@ -1,51 +1,79 @@
 use bevy::prelude::*;
 use lightyear::prelude::*;
 use shared::gameplay::{
+    balance::Balance,
     map::{CurrentMap, NodeType},
-    structures::{BaseTowerMarker, Tower, TowerStats},
+    structures::{BaseTowerMarker, Tower, TowerGenerationTimer, TowerStats},
 };
+use shared::protocol::MatchState;
 
-use crate::GameState;
-
-/// Plugin to add map initialization systems
+/// Plugin to spawn the replicated structures of the map when the match starts
+/// and remove them once it is over
 pub struct MapInitPlugin;
 
 impl Plugin for MapInitPlugin {
     fn build(&self, app: &mut App) {
-        // app.add_systems(OnEnter(GameState::GameRunning), spawn_map);
+        app.add_systems(Startup, spawn_generation_timer)
+            .add_systems(OnEnter(MatchState::MatchStarting), spawn_map)
+            .add_systems(OnExit(MatchState::InProgress), despawn_all_towers);
     }
 }
 
-pub fn spawn_map(mut commands: Commands, q_map: Query<&CurrentMap>) {
-    let map = q_map.single().unwrap().clone();
+fn spawn_generation_timer(mut commands: Commands, balance: Res<Balance>) {
+    commands.spawn((
+        TowerGenerationTimer::new(balance.regen_interval),
+        Replicate::to_clients(NetworkTarget::All),
+    ));
+}
+
+pub fn spawn_map(mut commands: Commands, q_map: Query<&CurrentMap>, balance: Res<Balance>) {
+    let Ok(map) = q_map.single() else {
+        error!("No map to spawn");
+        return;
+    };
+    println!("🏰 Spawning structures of map '{}'", map.0.name);
     // Spawn structures based on node type
     for (node_id, node) in &map.0.nodes {
         match &node.node_type {
             NodeType::StructureType(structure_type) => {
                 match structure_type {
                     shared::gameplay::structures::StructureType::Tower(owner) => {
-                        commands.spawn((
+                        // Neutral towers start with their configured garrison
+                        let mana = if owner.is_none() {
+                            node.neutral.garrison
+                        } else {
+                            0
+                        };
+                        let mut tower = commands.spawn((
                             Tower {
-                                mana: 0,
+                                mana,
                                 node_id: *node_id,
                                 owner: *owner,
                             },
-                            TowerStats::new(1),
+                            TowerStats::new(1, &balance),
                             Transform::from_translation(node.position.extend(0.0)),
                             GlobalTransform::default(),
+                            Replicate::to_clients(NetworkTarget::All),
+                            InterpolationTarget::to_clients(NetworkTarget::All),
                         ));
+                        if owner.is_none() {
+                            tower.insert(node.neutral);
+                        }
                     }
                     shared::gameplay::structures::StructureType::BaseTower(team_id) => {
+                        let stats = TowerStats::new(1, &balance);
                         commands.spawn((
                             Tower {
-                                mana: 30, // Base towers start with full mana
+                                mana: stats.max_mana(), // Base towers start with full mana
                                 node_id: *node_id,
                                 owner: Some(*team_id),
                             },
-                            TowerStats::new(1),
+                            stats,
                             BaseTowerMarker,
                             Transform::from_translation(node.position.extend(0.0)),
                             GlobalTransform::default(),
+                            Replicate::to_clients(NetworkTarget::All),
+                            InterpolationTarget::to_clients(NetworkTarget::All),
                         ));
                     }
                 }
@@ -58,7 +86,8 @@ pub fn spawn_map(mut commands: Commands, q_map: Query<&CurrentMap>) {
 }
 
 pub fn despawn_all_towers(mut commands: Commands, towers: Query<Entity, With<Tower>>) {
+    println!("🧹 Removing {} towers", towers.iter().count());
     for entity in towers.iter() {
-        commands.entity(entity).despawn_recursive();
+        commands.entity(entity).despawn();
     }
 }
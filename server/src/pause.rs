@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use shared::gameplay::{
+    balance::Balance,
+    speed::GameSpeed,
+    state::{CurrentGameState, GameState as SimulationState, PauseStatus},
+};
+use shared::protocol::{MatchState, RequestPause, RequestResume};
+use std::collections::HashMap;
+
+use crate::{ClientPlayer, ServerConfig};
+
+/// Plugin to spawn the replicated game state and let players pause and resume it
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseBudgets>()
+            .add_systems(Startup, spawn_game_state)
+            .add_systems(Update, (handle_pause_requests, tick_pause).chain());
+    }
+}
+
+/// Seconds of pause each player has used so far
+#[derive(Resource, Debug, Default)]
+struct PauseBudgets {
+    used: HashMap<u32, f32>,
+}
+
+impl PauseBudgets {
+    fn remaining(&self, player_id: u32, balance: &Balance) -> f32 {
+        balance.pause_budget - self.used.get(&player_id).copied().unwrap_or(0.0)
+    }
+}
+
+fn spawn_game_state(mut commands: Commands, config: Res<ServerConfig>) {
+    if config.game_speed != GameSpeed::default() {
+        println!("⏩ Running the simulation at {}x speed", config.game_speed.get());
+    }
+    commands.spawn((
+        CurrentGameState(SimulationState::Running),
+        PauseStatus::default(),
+        config.game_speed,
+        Replicate::to_clients(NetworkTarget::All),
+    ));
+}
+
+/// A pause is granted while the match is in progress and the game runs, or
+/// during an unpause countdown, which it cancels. Anyone may ask to resume,
+/// which starts the countdown.
+fn handle_pause_requests(
+    mut pause_receivers: Query<(&ClientPlayer, &mut MessageReceiver<RequestPause>)>,
+    mut resume_receivers: Query<(&ClientPlayer, &mut MessageReceiver<RequestResume>)>,
+    mut q_state: Query<(&mut CurrentGameState, &mut PauseStatus)>,
+    budgets: Res<PauseBudgets>,
+    balance: Res<Balance>,
+    match_state: Res<State<MatchState>>,
+) {
+    let Ok((mut state, mut status)) = q_state.single_mut() else {
+        return;
+    };
+    let in_progress = *match_state.get() == MatchState::InProgress;
+
+    for (player, mut receiver) in pause_receivers.iter_mut() {
+        for _ in receiver.receive() {
+            let counting_down = status.resume_in.is_some();
+            if !in_progress
+                || state.0 == SimulationState::Ended
+                || (state.0 == SimulationState::Paused && !counting_down)
+            {
+                continue;
+            }
+            let remaining = budgets.remaining(player.player_id, &balance);
+            if remaining <= 0.0 {
+                warn!(
+                    "Player {} tried to pause with no pause budget left",
+                    player.player_id
+                );
+                continue;
+            }
+
+            state.0 = SimulationState::Paused;
+            *status = PauseStatus {
+                paused_by: Some(player.player_id),
+                resume_in: None,
+            };
+            println!(
+                "⏸️ Player {} paused the match ({:.0}s of pause left)",
+                player.player_id, remaining
+            );
+        }
+    }
+
+    for (player, mut receiver) in resume_receivers.iter_mut() {
+        for _ in receiver.receive() {
+            if state.0 != SimulationState::Paused || status.resume_in.is_some() {
+                continue;
+            }
+            status.resume_in = Some(balance.unpause_countdown);
+            println!(
+                "▶️ Player {} asked to resume, resuming in {}s",
+                player.player_id, balance.unpause_countdown
+            );
+        }
+    }
+}
+
+/// Charges the pausing player's budget in real time and resumes the match once
+/// the countdown ends. Running out of budget starts the countdown.
+fn tick_pause(
+    time: Res<Time<Real>>,
+    mut q_state: Query<(&mut CurrentGameState, &mut PauseStatus)>,
+    mut budgets: ResMut<PauseBudgets>,
+    balance: Res<Balance>,
+) {
+    let Ok((mut state, mut status)) = q_state.single_mut() else {
+        return;
+    };
+    if state.0 != SimulationState::Paused {
+        return;
+    }
+    let delta = time.delta_secs();
+
+    if let Some(player_id) = status.paused_by {
+        *budgets.used.entry(player_id).or_default() += delta;
+        if status.resume_in.is_none() && budgets.remaining(player_id, &balance) <= 0.0 {
+            println!(
+                "⌛ Player {} is out of pause budget, resuming in {}s",
+                player_id, balance.unpause_countdown
+            );
+            status.resume_in = Some(balance.unpause_countdown);
+        }
+    }
+
+    if let Some(resume_in) = status.resume_in {
+        let resume_in = resume_in - delta;
+        if resume_in <= 0.0 {
+            state.0 = SimulationState::Running;
+            *status = PauseStatus::default();
+            println!("▶️ Match resumed");
+        } else {
+            status.resume_in = Some(resume_in);
+        }
+    }
+}
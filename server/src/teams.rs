@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use lightyear::prelude::server::*;
+use lightyear::prelude::*;
+use shared::gameplay::{
+    abilities::AbilityCooldowns, map::CurrentMap, structures::TeamId, teams::Team,
+};
+
+use crate::{ClientPlayer, ServerConfig};
+
+/// Plugin to spawn one replicated `Team` per base on the map and keep its
+/// member list in sync with connected players
+pub struct TeamSetupPlugin;
+
+impl Plugin for TeamSetupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (spawn_teams, add_team_members).chain());
+    }
+}
+
+/// Alliances come from `ServerConfig::alliances`. Teams not listed in any
+/// alliance play on their own, so an empty list is a free-for-all.
+fn spawn_teams(
+    mut commands: Commands,
+    config: Res<ServerConfig>,
+    q_map: Query<&CurrentMap, Changed<CurrentMap>>,
+    q_teams: Query<Entity, With<Team>>,
+    q_players: Query<&ClientPlayer>,
+) {
+    let Ok(map) = q_map.single() else {
+        return;
+    };
+
+    for entity in q_teams.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let team_ids: Vec<TeamId> = map.0.base_towers().into_iter().map(|(team, _)| team).collect();
+    for &id in &team_ids {
+        let mut team = Team::new(id);
+        team.allies = config
+            .alliances
+            .iter()
+            .filter(|alliance| alliance.contains(&id))
+            .flatten()
+            .copied()
+            .filter(|&ally| ally != id && team_ids.contains(&ally))
+            .collect();
+        team.allies.sort_unstable();
+        team.allies.dedup();
+        team.members = q_players
+            .iter()
+            .filter(|player| player.team == id)
+            .map(|player| player.player_id)
+            .collect();
+
+        println!(
+            "🚩 Team {} (allies: {:?}, players: {:?})",
+            team.id, team.allies, team.members
+        );
+        commands.spawn((
+            team,
+            AbilityCooldowns::default(),
+            Replicate::to_clients(NetworkTarget::All),
+        ));
+    }
+}
+
+fn add_team_members(
+    q_new_players: Query<&ClientPlayer, Added<ClientPlayer>>,
+    mut q_teams: Query<&mut Team>,
+) {
+    for player in q_new_players.iter() {
+        let Some(mut team) = q_teams.iter_mut().find(|team| team.id == player.team) else {
+            warn!(
+                "Player {} was assigned to team {}, which is not on this map",
+                player.player_id, player.team
+            );
+            continue;
+        };
+        if !team.members.contains(&player.player_id) {
+            team.members.push(player.player_id);
+        }
+    }
+}
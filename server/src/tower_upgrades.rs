@@ -0,0 +1,58 @@
+use bevy::prelude::*;
+use shared::gameplay::{
+    balance::Balance,
+    structures::{Tower, TowerStats, TowerUpgrading},
+};
+use shared::protocol::{RejectionReason, UpgradeTower};
+
+use crate::command_validation::{Accepted, CommandSet, Rejected};
+
+/// Plugin to start validated tower upgrades
+pub struct TowerUpgradePlugin;
+
+impl Plugin for TowerUpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, handle_upgrade_tower.in_set(CommandSet::Apply));
+    }
+}
+
+fn handle_upgrade_tower(
+    mut commands: Commands,
+    mut accepted: EventReader<Accepted<UpgradeTower>>,
+    mut q_towers: Query<(Entity, &mut Tower, &TowerStats)>,
+    balance: Res<Balance>,
+    mut rejected: EventWriter<Rejected>,
+) {
+    for accepted in accepted.read() {
+        let Accepted {
+            player, command, ..
+        } = accepted;
+        let Some((entity, mut tower, stats)) = q_towers
+            .iter_mut()
+            .find(|(_, tower, _)| tower.node_id == command.node_id)
+        else {
+            rejected.write(accepted.reject(RejectionReason::NoTower(command.node_id)));
+            continue;
+        };
+        let Some(cost) = balance.upgrade_cost(stats.level()) else {
+            rejected.write(accepted.reject(RejectionReason::MaxLevel(command.node_id)));
+            continue;
+        };
+        if let Err(rejection) = accepted.pay(&mut tower, cost.mana) {
+            rejected.write(rejection);
+            continue;
+        }
+
+        commands.entity(entity).insert(TowerUpgrading {
+            target_level: stats.level() + 1,
+            remaining: cost.build_time,
+        });
+        info!(
+            "🔨 Team {} upgrading tower {} to level {} ({}s)",
+            player.team,
+            command.node_id,
+            stats.level() + 1,
+            cost.build_time
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use shared::gameplay::{routing::RouteTable, structures::Tower, troops::TroopGroup};
+use shared::protocol::{RejectionReason, SendTroops};
+
+use crate::command_validation::{Accepted, CommandSet, Rejected};
+
+/// Plugin to turn validated `SendTroops` requests into travelling troop groups
+pub struct TroopDispatchPlugin;
+
+impl Plugin for TroopDispatchPlugin {
+    fn build(&self, app: &mut App) {
+        // Validation already checked that the match is running
+        app.add_systems(Update, handle_send_troops.in_set(CommandSet::Apply));
+    }
+}
+
+fn handle_send_troops(
+    mut commands: Commands,
+    mut accepted: EventReader<Accepted<SendTroops>>,
+    mut q_towers: Query<&mut Tower>,
+    route_table: Res<RouteTable>,
+    mut rejected: EventWriter<Rejected>,
+) {
+    for accepted in accepted.read() {
+        let Accepted {
+            player, command, ..
+        } = accepted;
+        let Some(route) = route_table.route(command.from, command.to) else {
+            rejected.write(accepted.reject(RejectionReason::Unreachable {
+                from: command.from,
+                to: command.to,
+            }));
+            continue;
+        };
+        let Some(mut tower) = q_towers
+            .iter_mut()
+            .find(|tower| tower.node_id == command.from)
+        else {
+            rejected.write(accepted.reject(RejectionReason::NoTower(command.from)));
+            continue;
+        };
+        if let Err(rejection) = accepted.pay(&mut tower, command.amount) {
+            rejected.write(rejection);
+            continue;
+        }
+
+        info!(
+            "⚔️ Team {} sends {} troops from {} to {} ({:.0} units)",
+            player.team, command.amount, command.from, command.to, route.distance
+        );
+
+        commands.spawn((
+            TroopGroup::new(player.team, command.amount, route.nodes),
+            Replicate::to_clients(NetworkTarget::All),
+            InterpolationTarget::to_clients(NetworkTarget::All),
+        ));
+    }
+}
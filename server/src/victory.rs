@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use shared::gameplay::{
+    map::{CurrentMap, NodeType},
+    match_clock::{MatchClock, MatchPhase},
+    state::{CurrentGameState, GameState as SimulationState, run_if_game_running},
+    structures::{StructureType, TeamId, Tower},
+    teams::TeamRegistry,
+    troops::TroopGroup,
+};
+use shared::protocol::{CommandChannel, MatchEnded, MatchState};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{ClientPlayer, MatchCompleteWebhook, ServerConfig};
+
+/// Plugin to end the match once one alliance is left or the match clock runs out
+pub struct VictoryPlugin;
+
+impl Plugin for VictoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MatchDecided>();
+        app.add_systems(
+            Update,
+            (check_for_winner, check_time_limit, finish_match)
+                .chain()
+                .run_if(in_state(MatchState::InProgress).and(run_if_game_running)),
+        );
+    }
+}
+
+/// Raised once the outcome is known. `winners` is empty on a draw.
+#[derive(Event, Clone, Debug, PartialEq)]
+struct MatchDecided {
+    winners: Vec<TeamId>,
+}
+
+/// A team is eliminated when it no longer owns its base tower, or when it owns
+/// no towers and has no troops in flight. Teams without a base on the map are
+/// only subject to the second rule. The match ends when all surviving teams are
+/// allied, and the whole alliance wins, including its eliminated members.
+fn check_for_winner(
+    alliances: Res<TeamRegistry>,
+    q_map: Query<&CurrentMap>,
+    q_towers: Query<&Tower>,
+    q_troops: Query<&TroopGroup>,
+    mut decided: EventWriter<MatchDecided>,
+) {
+    let Ok(map) = q_map.single() else {
+        return;
+    };
+    if q_towers.is_empty() {
+        // Structures have not been spawned yet
+        return;
+    }
+
+    let bases: Vec<(TeamId, _)> = map
+        .0
+        .nodes
+        .values()
+        .filter_map(|node| match node.node_type {
+            NodeType::StructureType(StructureType::BaseTower(team)) => Some((team, node.id)),
+            _ => None,
+        })
+        .collect();
+
+    let mut teams: BTreeSet<TeamId> = bases.iter().map(|(team, _)| *team).collect();
+    teams.extend(q_towers.iter().filter_map(|tower| tower.owner));
+    teams.extend(q_troops.iter().map(|group| group.owner));
+
+    let surviving: Vec<TeamId> = teams
+        .iter()
+        .copied()
+        .filter(|&team| {
+            let lost_base = bases.iter().any(|&(base_team, node_id)| {
+                base_team == team
+                    && !q_towers
+                        .iter()
+                        .any(|tower| tower.node_id == node_id && tower.owner == Some(team))
+            });
+            let has_presence = q_towers.iter().any(|tower| tower.owner == Some(team))
+                || q_troops.iter().any(|group| group.owner == team);
+            !lost_base && has_presence
+        })
+        .collect();
+
+    let winners: Vec<TeamId> = match surviving.first() {
+        Some(&leader) => {
+            let one_alliance = surviving
+                .iter()
+                .all(|&team| alliances.are_allied(leader, team));
+            if !one_alliance {
+                return;
+            }
+            teams
+                .iter()
+                .copied()
+                .filter(|&team| alliances.are_allied(leader, team))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    decided.write(MatchDecided { winners });
+}
+
+/// Once sudden death is over, the alliance holding the most towers wins, with
+/// total tower mana breaking ties. A tie on both is a draw.
+fn check_time_limit(
+    alliances: Res<TeamRegistry>,
+    q_clock: Query<&MatchClock>,
+    q_towers: Query<&Tower>,
+    mut decided: EventWriter<MatchDecided>,
+) {
+    let Ok(clock) = q_clock.single() else {
+        return;
+    };
+    if clock.phase() != MatchPhase::Expired {
+        return;
+    }
+
+    // Each alliance is keyed by its lowest team id
+    let mut teams: BTreeSet<TeamId> = alliances.iter().map(|team| team.id).collect();
+    teams.extend(q_towers.iter().filter_map(|tower| tower.owner));
+    let leader = |team: TeamId| {
+        teams
+            .iter()
+            .copied()
+            .find(|&other| alliances.are_allied(team, other))
+            .unwrap_or(team)
+    };
+
+    let mut scores: BTreeMap<TeamId, (usize, u32)> = BTreeMap::new();
+    for tower in q_towers.iter() {
+        let Some(owner) = tower.owner else {
+            continue;
+        };
+        let score = scores.entry(leader(owner)).or_default();
+        score.0 += 1;
+        score.1 += tower.mana as u32;
+    }
+    println!("⏰ Time limit reached, alliance scores (towers, mana): {:?}", scores);
+
+    let best = scores.values().max().copied();
+    let leaders: Vec<TeamId> = scores
+        .iter()
+        .filter(|(_, score)| Some(**score) == best)
+        .map(|(&team, _)| team)
+        .collect();
+    let winners = match leaders[..] {
+        [winner] => teams
+            .iter()
+            .copied()
+            .filter(|&team| alliances.are_allied(winner, team))
+            .collect(),
+        _ => Vec::new(),
+    };
+    decided.write(MatchDecided { winners });
+}
+
+fn finish_match(
+    mut decided: EventReader<MatchDecided>,
+    mut q_state: Query<&mut CurrentGameState>,
+    mut next_state: ResMut<NextState<MatchState>>,
+    config: Res<ServerConfig>,
+    mut q_senders: Query<&mut MessageSender<MatchEnded>, With<ClientPlayer>>,
+) {
+    // Elimination takes precedence when both checks fire in the same frame
+    let Some(MatchDecided { winners }) = decided.read().next().cloned() else {
+        return;
+    };
+    decided.clear();
+    println!("🏁 Match over! Winning teams: {:?}", winners);
+
+    if let Ok(mut state) = q_state.single_mut() {
+        state.0 = SimulationState::Ended;
+    }
+    next_state.set(MatchState::Completed);
+
+    for mut sender in q_senders.iter_mut() {
+        sender.send::<CommandChannel>(MatchEnded {
+            winners: winners.clone(),
+        });
+    }
+
+    // Team n is played by the n-th expected player, whether or not they are
+    // still connected
+    let winning_players: Vec<u32> = config
+        .expected_players
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| winners.contains(&(*index as TeamId + 1)))
+        .map(|(_, &player_id)| player_id)
+        .collect();
+    notify_match_complete(&config, winning_players);
+}
+
+fn notify_match_complete(config: &ServerConfig, winners: Vec<u32>) {
+    if config.backend_url.is_empty() {
+        println!("No backend configured, skipping match-complete webhook");
+        return;
+    }
+
+    let url = format!("{}/webhooks/match-complete", config.backend_url);
+    let webhook_data = MatchCompleteWebhook {
+        match_id: config.match_id,
+        winner: winners.first().copied(),
+        winners,
+    };
+
+    // Use a blocking client on its own thread so the game loop is not held up
+    std::thread::spawn(move || {
+        let client = reqwest::blocking::Client::new();
+        match client.post(&url).json(&webhook_data).send() {
+            Ok(response) => {
+                if response.status().is_success() {
+                    println!("✅ Successfully notified backend that the match is complete");
+                } else {
+                    eprintln!("⚠️ Failed to notify backend: {}", response.status());
+                }
+            }
+            Err(e) => eprintln!("Failed to notify backend: {}", e),
+        }
+    });
+}
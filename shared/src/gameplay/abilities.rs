@@ -0,0 +1,115 @@
+use crate::gameplay::{
+    speed::GameTime,
+    state::{SimulationSet, run_if_game_running},
+};
+use crate::protocol::AppProtocolExt;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Ability {
+    /// Stops mana regeneration of an enemy tower
+    Freeze,
+    /// Doubles mana regeneration of every tower of the casting team
+    ManaSurge,
+    /// Keeps a friendly tower from being captured
+    Shield,
+}
+
+/// A tower that does not regenerate mana while this is present.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Frozen {
+    pub remaining: f32,
+}
+
+/// A tower that cannot be captured while this is present. Attackers still
+/// reduce its garrison, down to zero.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Shielded {
+    pub remaining: f32,
+}
+
+/// Placed on a `Team` entity whose towers regenerate twice as fast.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ManaSurge {
+    pub remaining: f32,
+}
+
+/// Seconds left until each ability can be cast again, stored on the `Team`.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct AbilityCooldowns {
+    remaining: BTreeMap<Ability, f32>,
+}
+
+impl AbilityCooldowns {
+    pub fn remaining(&self, ability: Ability) -> f32 {
+        self.remaining.get(&ability).copied().unwrap_or(0.0)
+    }
+
+    pub fn is_ready(&self, ability: Ability) -> bool {
+        self.remaining(ability) <= 0.0
+    }
+
+    pub fn start(&mut self, ability: Ability, cooldown: f32) {
+        self.remaining.insert(ability, cooldown);
+    }
+
+    fn tick(&mut self, delta: f32) {
+        self.remaining.retain(|_, remaining| {
+            *remaining -= delta;
+            *remaining > 0.0
+        });
+    }
+}
+
+fn tick_ability_effects(
+    mut commands: Commands,
+    time: GameTime,
+    mut q_frozen: Query<(Entity, &mut Frozen)>,
+    mut q_shielded: Query<(Entity, &mut Shielded)>,
+    mut q_surging: Query<(Entity, &mut ManaSurge)>,
+    mut q_cooldowns: Query<&mut AbilityCooldowns>,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut frozen) in q_frozen.iter_mut() {
+        frozen.remaining -= delta;
+        if frozen.remaining <= 0.0 {
+            commands.entity(entity).remove::<Frozen>();
+        }
+    }
+    for (entity, mut shielded) in q_shielded.iter_mut() {
+        shielded.remaining -= delta;
+        if shielded.remaining <= 0.0 {
+            commands.entity(entity).remove::<Shielded>();
+        }
+    }
+    for (entity, mut surge) in q_surging.iter_mut() {
+        surge.remaining -= delta;
+        if surge.remaining <= 0.0 {
+            commands.entity(entity).remove::<ManaSurge>();
+        }
+    }
+    for mut cooldowns in q_cooldowns.iter_mut() {
+        if !cooldowns.remaining.is_empty() {
+            cooldowns.tick(delta);
+        }
+    }
+}
+
+pub struct AbilityPlugin;
+
+impl Plugin for AbilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            tick_ability_effects
+                .in_set(SimulationSet)
+                .run_if(run_if_game_running),
+        );
+        app.register_replicated::<Frozen>("Frozen");
+        app.register_replicated::<Shielded>("Shielded");
+        app.register_replicated::<ManaSurge>("ManaSurge");
+        app.register_replicated::<AbilityCooldowns>("AbilityCooldowns");
+    }
+}
@@ -0,0 +1,309 @@
+use crate::gameplay::abilities::Ability;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Every tunable gameplay number. The server loads this from `balance.ron` and
+/// sends it to clients; `Default` is the fallback used until the asset is loaded.
+#[derive(Asset, TypePath, Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Balance {
+    /// Seconds between two mana generation ticks
+    pub regen_interval: f32,
+    /// Distance a troop group covers per second, in map units
+    pub troop_speed: f32,
+    /// Stats per tower level, starting at level 1
+    pub tower_levels: Vec<TowerLevelBalance>,
+    /// Costs and timings of team abilities
+    #[serde(default)]
+    pub abilities: AbilityBalance,
+    /// Seconds of regular time before sudden death
+    #[serde(default = "default_match_duration")]
+    pub match_duration: f32,
+    /// Seconds of sudden death before the match is decided on points
+    #[serde(default = "default_sudden_death_duration")]
+    pub sudden_death_duration: f32,
+    /// Seconds each player may keep the game paused over the whole match
+    #[serde(default = "default_pause_budget")]
+    pub pause_budget: f32,
+    /// Seconds between a resume request and the game actually resuming
+    #[serde(default = "default_unpause_countdown")]
+    pub unpause_countdown: f32,
+}
+
+fn default_match_duration() -> f32 {
+    600.0
+}
+
+fn default_sudden_death_duration() -> f32 {
+    60.0
+}
+
+fn default_pause_budget() -> f32 {
+    90.0
+}
+
+fn default_unpause_countdown() -> f32 {
+    3.0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TowerLevelBalance {
+    pub max_mana: u8,
+    pub regen_rate: f32,
+    pub overflow_degen_rate: f32,
+    /// Cost to upgrade from this level to the next one, `None` for the last level
+    pub upgrade: Option<UpgradeCost>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct UpgradeCost {
+    pub mana: u8,
+    /// Seconds the tower spends upgrading
+    pub build_time: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AbilityCost {
+    /// Mana taken from the casting tower
+    pub mana: u8,
+    /// Seconds before the team can cast the ability again
+    pub cooldown: f32,
+    /// Seconds the effect lasts
+    pub duration: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AbilityBalance {
+    pub freeze: AbilityCost,
+    pub mana_surge: AbilityCost,
+    pub shield: AbilityCost,
+}
+
+impl Default for AbilityBalance {
+    fn default() -> Self {
+        let cost = |mana, cooldown, duration| AbilityCost {
+            mana,
+            cooldown,
+            duration,
+        };
+        Self {
+            freeze: cost(15, 30.0, 8.0),
+            mana_surge: cost(20, 45.0, 10.0),
+            shield: cost(10, 25.0, 6.0),
+        }
+    }
+}
+
+impl Balance {
+    pub fn max_tower_level(&self) -> u8 {
+        self.tower_levels.len().clamp(1, u8::MAX as usize) as u8
+    }
+
+    /// Balance entry for `level`, clamped into the available levels. Needs at
+    /// least one level, which `validate` makes sure of.
+    pub fn tower_level(&self, level: u8) -> &TowerLevelBalance {
+        let index = level.clamp(1, self.max_tower_level()) as usize - 1;
+        &self.tower_levels[index]
+    }
+
+    /// Cost of upgrading a tower currently at `level`, or `None` at the top level.
+    pub fn upgrade_cost(&self, level: u8) -> Option<UpgradeCost> {
+        if level >= self.max_tower_level() {
+            return None;
+        }
+        self.tower_level(level).upgrade
+    }
+
+    pub fn ability(&self, ability: Ability) -> AbilityCost {
+        match ability {
+            Ability::Freeze => self.abilities.freeze,
+            Ability::ManaSurge => self.abilities.mana_surge,
+            Ability::Shield => self.abilities.shield,
+        }
+    }
+
+    /// Checks for values the simulation cannot run with. Returns every problem found.
+    pub fn validate(&self) -> Result<(), Vec<BalanceError>> {
+        let mut errors = Vec::new();
+        let mut positive = |field: String, value: f32| {
+            if !value.is_finite() || value <= 0.0 {
+                errors.push(BalanceError::NotPositive { field });
+            }
+        };
+
+        positive("regen_interval".into(), self.regen_interval);
+        positive("troop_speed".into(), self.troop_speed);
+        positive("match_duration".into(), self.match_duration);
+        for (i, level) in self.tower_levels.iter().enumerate() {
+            if let Some(upgrade) = level.upgrade {
+                positive(
+                    format!("tower_levels[{}].upgrade.build_time", i),
+                    upgrade.build_time,
+                );
+            }
+        }
+        for ability in [Ability::Freeze, Ability::ManaSurge, Ability::Shield] {
+            positive(
+                format!("abilities.{:?}.duration", ability),
+                self.ability(ability).duration,
+            );
+        }
+
+        let mut non_negative = |field: String, value: f32| {
+            if !value.is_finite() || value < 0.0 {
+                errors.push(BalanceError::Negative { field });
+            }
+        };
+        non_negative("sudden_death_duration".into(), self.sudden_death_duration);
+        non_negative("pause_budget".into(), self.pause_budget);
+        non_negative("unpause_countdown".into(), self.unpause_countdown);
+        for (i, level) in self.tower_levels.iter().enumerate() {
+            non_negative(format!("tower_levels[{}].regen_rate", i), level.regen_rate);
+            non_negative(
+                format!("tower_levels[{}].overflow_degen_rate", i),
+                level.overflow_degen_rate,
+            );
+        }
+        for ability in [Ability::Freeze, Ability::ManaSurge, Ability::Shield] {
+            non_negative(
+                format!("abilities.{:?}.cooldown", ability),
+                self.ability(ability).cooldown,
+            );
+        }
+
+        if self.tower_levels.is_empty() {
+            errors.push(BalanceError::NoTowerLevels);
+        }
+        for (i, pair) in self.tower_levels.windows(2).enumerate() {
+            if pair[1].max_mana <= pair[0].max_mana {
+                errors.push(BalanceError::LevelNotIncreasing { level: i as u8 + 2 });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// A single problem found by `Balance::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BalanceError {
+    NoTowerLevels,
+    /// The level does not hold more mana than the one below it
+    LevelNotIncreasing { level: u8 },
+    /// A duration or speed that is zero, negative or not finite
+    NotPositive { field: String },
+    /// A rate or duration that is negative or not finite
+    Negative { field: String },
+}
+
+impl fmt::Display for BalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoTowerLevels => write!(f, "There are no tower levels"),
+            Self::LevelNotIncreasing { level } => write!(
+                f,
+                "Tower level {} does not hold more mana than level {}",
+                level,
+                level - 1
+            ),
+            Self::NotPositive { field } => write!(f, "{} must be greater than zero", field),
+            Self::Negative { field } => write!(f, "{} must not be negative", field),
+        }
+    }
+}
+
+impl std::error::Error for BalanceError {}
+
+impl Default for Balance {
+    fn default() -> Self {
+        let level = |max_mana, regen_rate, overflow_degen_rate, upgrade| TowerLevelBalance {
+            max_mana,
+            regen_rate,
+            overflow_degen_rate,
+            upgrade,
+        };
+        let upgrade = |mana, build_time| Some(UpgradeCost { mana, build_time });
+
+        Self {
+            regen_interval: 1.0,
+            troop_speed: 60.0,
+            tower_levels: vec![
+                level(30, 2.0, 6.0, upgrade(10, 3.0)),
+                level(45, 3.0, 7.0, upgrade(20, 5.0)),
+                level(60, 4.0, 8.0, upgrade(30, 7.0)),
+                level(80, 5.0, 10.0, upgrade(40, 9.0)),
+                level(100, 6.0, 12.0, None),
+            ],
+            abilities: AbilityBalance::default(),
+            match_duration: default_match_duration(),
+            sudden_death_duration: default_sudden_death_duration(),
+            pause_budget: default_pause_budget(),
+            unpause_countdown: default_unpause_countdown(),
+        }
+    }
+}
+
+pub struct BalancePlugin;
+
+impl Plugin for BalancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Balance>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_balance_is_valid() {
+        assert_eq!(Balance::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_empty_tower_levels() {
+        let balance = Balance {
+            tower_levels: Vec::new(),
+            ..default()
+        };
+        assert_eq!(balance.validate(), Err(vec![BalanceError::NoTowerLevels]));
+    }
+
+    #[test]
+    fn rejects_levels_that_do_not_grow() {
+        let mut balance = Balance::default();
+        balance.tower_levels[2].max_mana = balance.tower_levels[1].max_mana;
+        assert_eq!(
+            balance.validate(),
+            Err(vec![BalanceError::LevelNotIncreasing { level: 3 }])
+        );
+    }
+
+    #[test]
+    fn rejects_zero_durations_and_negative_speeds() {
+        let balance = Balance {
+            regen_interval: 0.0,
+            troop_speed: -60.0,
+            pause_budget: f32::NAN,
+            ..default()
+        };
+        assert_eq!(
+            balance.validate(),
+            Err(vec![
+                BalanceError::NotPositive {
+                    field: "regen_interval".into()
+                },
+                BalanceError::NotPositive {
+                    field: "troop_speed".into()
+                },
+                BalanceError::Negative {
+                    field: "pause_budget".into()
+                },
+            ])
+        );
+    }
+}
@@ -0,0 +1,486 @@
+use crate::gameplay::{
+    abilities::Shielded,
+    map::{CurrentMap, NodeId},
+    state::{SimulationSet, run_if_game_running},
+    structures::{BaseTowerMarker, NeutralGarrison, TeamId, Tower},
+    teams::TeamRegistry,
+    troops::{TroopGroup, TroopGroupArrived, move_troop_groups},
+};
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+
+/// Sent when a tower changes hands because an attacking troop group emptied its garrison.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct TowerCaptured {
+    pub node_id: NodeId,
+    pub previous_owner: Option<TeamId>,
+    pub new_owner: TeamId,
+}
+
+/// Groups of the owner or its allies reinforce the tower, which may push it above
+/// `max_mana`. Hostile groups fight the garrison and take the tower if they
+/// outnumber it, unless the tower is shielded.
+fn resolve_troop_arrivals(
+    teams: Res<TeamRegistry>,
+    mut arrivals: EventReader<TroopGroupArrived>,
+    mut q_towers: Query<(&mut Tower, Has<Shielded>)>,
+    mut captured: EventWriter<TowerCaptured>,
+) {
+    for arrival in arrivals.read() {
+        let group = &arrival.group;
+        let Some((mut tower, shielded)) = q_towers
+            .iter_mut()
+            .find(|(tower, _)| tower.node_id == arrival.node_id)
+        else {
+            info!(
+                "{} troops of team {} dispersed at waypoint {}",
+                group.amount, group.owner, arrival.node_id
+            );
+            continue;
+        };
+
+        if teams.is_friendly(group.owner, tower.owner) {
+            tower.mana = tower.mana.saturating_add(group.amount);
+            info!(
+                "Team {} reinforced tower {} with {} troops (now {})",
+                group.owner, tower.node_id, group.amount, tower.mana
+            );
+        } else if shielded && group.amount >= tower.mana {
+            tower.mana = 0;
+            info!(
+                "🛡️ Shield kept tower {} from being captured by team {}",
+                tower.node_id, group.owner
+            );
+        } else if group.amount > tower.mana {
+            let previous_owner = tower.owner;
+            tower.mana = group.amount - tower.mana;
+            tower.owner = Some(group.owner);
+            info!(
+                "🏰 Team {} captured tower {} from {:?} with {} troops left",
+                group.owner, tower.node_id, previous_owner, tower.mana
+            );
+            captured.write(TowerCaptured {
+                node_id: tower.node_id,
+                previous_owner,
+                new_owner: group.owner,
+            });
+        } else {
+            tower.mana -= group.amount;
+            info!(
+                "Tower {} held against {} troops of team {} ({} left)",
+                tower.node_id, group.amount, group.owner, tower.mana
+            );
+        }
+    }
+}
+
+/// Sent when two hostile troop groups meet on an edge and fight.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct TroopGroupsClashed {
+    pub edge: (NodeId, NodeId),
+    pub teams: (TeamId, TeamId),
+    pub losses: u8,
+}
+
+/// A group's place on an edge, measured from the edge's lower node id so that
+/// groups heading in opposite directions share one coordinate.
+struct EdgeOccupant {
+    entity: Entity,
+    owner: TeamId,
+    amount: u8,
+    starting_amount: u8,
+    offset: f32,
+}
+
+/// Opposing hostile groups on the same edge cancel out once their positions
+/// cross. Allied groups pass each other.
+/// Groups are processed in a fixed order (edge, then position, then entity) so
+/// the server always produces the same outcome for the same state.
+fn resolve_edge_collisions(
+    mut commands: Commands,
+    teams: Res<TeamRegistry>,
+    q_map: Query<&CurrentMap>,
+    mut q_groups: Query<(Entity, &mut TroopGroup)>,
+    mut clashes: EventWriter<TroopGroupsClashed>,
+) {
+    let Ok(map) = q_map.single() else {
+        return;
+    };
+
+    // Edge (low, high) -> (groups moving low -> high, groups moving high -> low)
+    let mut edges: BTreeMap<(NodeId, NodeId), (Vec<EdgeOccupant>, Vec<EdgeOccupant>)> =
+        BTreeMap::new();
+    for (entity, group) in q_groups.iter() {
+        let Some((from, to)) = group.current_edge() else {
+            continue;
+        };
+        let Some(length) = map.0.edge_length(from, to) else {
+            continue;
+        };
+        let key = (from.min(to), from.max(to));
+        let lanes = edges.entry(key).or_default();
+        if from < to {
+            lanes.0.push(EdgeOccupant {
+                entity,
+                owner: group.owner,
+                amount: group.amount,
+                starting_amount: group.amount,
+                offset: group.progress,
+            });
+        } else {
+            lanes.1.push(EdgeOccupant {
+                entity,
+                owner: group.owner,
+                amount: group.amount,
+                starting_amount: group.amount,
+                offset: length - group.progress,
+            });
+        }
+    }
+
+    for (edge, (mut forward, mut backward)) in edges {
+        if forward.is_empty() || backward.is_empty() {
+            continue;
+        }
+        // Front-most groups first: forward groups lead with the largest offset,
+        // backward groups with the smallest.
+        forward.sort_by(|a, b| b.offset.total_cmp(&a.offset).then(a.entity.cmp(&b.entity)));
+        backward.sort_by(|a, b| a.offset.total_cmp(&b.offset).then(a.entity.cmp(&b.entity)));
+
+        for attacker in forward.iter_mut() {
+            for defender in backward.iter_mut() {
+                if attacker.amount == 0 {
+                    break;
+                }
+                if defender.amount == 0
+                    || teams.are_allied(attacker.owner, defender.owner)
+                    || attacker.offset < defender.offset
+                {
+                    continue;
+                }
+
+                let losses = attacker.amount.min(defender.amount);
+                attacker.amount -= losses;
+                defender.amount -= losses;
+                clashes.write(TroopGroupsClashed {
+                    edge,
+                    teams: (attacker.owner, defender.owner),
+                    losses,
+                });
+                info!(
+                    "⚔️ Teams {} and {} clashed on edge {:?}, each losing {}",
+                    attacker.owner, defender.owner, edge, losses
+                );
+            }
+        }
+
+        let fought = forward
+            .iter()
+            .chain(backward.iter())
+            .filter(|occupant| occupant.amount != occupant.starting_amount);
+        for occupant in fought {
+            if occupant.amount == 0 {
+                commands.entity(occupant.entity).despawn();
+            } else if let Ok((_, mut group)) = q_groups.get_mut(occupant.entity) {
+                group.amount = occupant.amount;
+            }
+        }
+    }
+}
+
+/// Sent when a neutral tower throws its garrison at an adjacent owned tower.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub struct TowerRaided {
+    pub raider: NodeId,
+    pub node_id: NodeId,
+    pub previous_owner: TeamId,
+    /// The raid emptied the garrison and the tower turned neutral
+    pub captured: bool,
+}
+
+/// A neutral tower with `raid_below` set attacks once it has regenerated to its
+/// cap, sending its whole garrison at the weakest adjacent owned tower holding
+/// less than `raid_below` mana. Base and shielded towers are never raided.
+/// Raiders act in node id order so the outcome is deterministic.
+fn resolve_neutral_raids(
+    q_map: Query<&CurrentMap>,
+    mut q_towers: Query<(
+        &mut Tower,
+        Option<&NeutralGarrison>,
+        Has<BaseTowerMarker>,
+        Has<Shielded>,
+    )>,
+    mut raided: EventWriter<TowerRaided>,
+) {
+    let Ok(map) = q_map.single() else {
+        return;
+    };
+
+    let mut raiders: Vec<(NodeId, u8)> = q_towers
+        .iter()
+        .filter_map(|(tower, neutral, _, _)| {
+            let neutral = neutral?;
+            let raid_below = neutral.raid_below?;
+            let ready =
+                tower.owner.is_none() && tower.mana > 0 && tower.mana >= neutral.regen_cap;
+            ready.then_some((tower.node_id, raid_below))
+        })
+        .collect();
+    raiders.sort_unstable();
+
+    for (raider, raid_below) in raiders {
+        let Some(node) = map.0.get_node(raider) else {
+            continue;
+        };
+        let target = q_towers
+            .iter()
+            .filter(|(tower, _, is_base, shielded)| {
+                !is_base
+                    && !shielded
+                    && tower.owner.is_some()
+                    && tower.mana < raid_below
+                    && node.connected_to.contains(&tower.node_id)
+            })
+            .map(|(tower, _, _, _)| (tower.mana, tower.node_id))
+            .min();
+        let Some((_, target)) = target else {
+            continue;
+        };
+
+        let mut attack = 0;
+        for (mut tower, _, _, _) in q_towers.iter_mut() {
+            if tower.node_id == raider {
+                attack = tower.mana;
+                tower.mana = 0;
+            }
+        }
+        for (mut tower, _, _, _) in q_towers.iter_mut() {
+            let Some(previous_owner) = tower.owner.filter(|_| tower.node_id == target) else {
+                continue;
+            };
+            let captured = attack > tower.mana;
+            if captured {
+                tower.mana = attack - tower.mana;
+                tower.owner = None;
+            } else {
+                tower.mana -= attack;
+            }
+            info!(
+                "Neutral tower {} raided tower {} of team {} with {} troops (captured: {})",
+                raider, target, previous_owner, attack, captured
+            );
+            raided.write(TowerRaided {
+                raider,
+                node_id: target,
+                previous_owner,
+                captured,
+            });
+        }
+    }
+}
+
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TowerCaptured>();
+        app.add_event::<TroopGroupsClashed>();
+        app.add_event::<TowerRaided>();
+        app.add_systems(
+            Update,
+            (
+                resolve_edge_collisions,
+                resolve_troop_arrivals,
+                resolve_neutral_raids,
+            )
+                .chain()
+                .after(move_troop_groups)
+                .in_set(SimulationSet)
+                .run_if(run_if_game_running),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::balance::Balance;
+    use crate::gameplay::map::{Map, MapFile, MapNode, NodeType};
+    use crate::gameplay::structures::TowerStats;
+
+    /// Combat systems without the simulation run conditions, on a single
+    /// 100 unit edge between nodes 1 and 2
+    fn app() -> App {
+        let mut app = App::new();
+        app.init_resource::<TeamRegistry>()
+            .add_event::<TroopGroupArrived>()
+            .add_event::<TowerCaptured>()
+            .add_event::<TroopGroupsClashed>()
+            .add_systems(
+                Update,
+                (resolve_edge_collisions, resolve_troop_arrivals).chain(),
+            );
+        let node = |id: NodeId, x: f32, to: NodeId| MapNode {
+            id,
+            connected_to: vec![to],
+            position: Vec2::new(x, 0.0),
+            node_type: NodeType::Waypoint,
+            neutral: NeutralGarrison::NONE,
+        };
+        app.world_mut()
+            .spawn(CurrentMap(Map::from_file_unchecked(MapFile {
+                name: "Test".to_string(),
+                nodes: vec![node(1, 0.0, 2), node(2, 100.0, 1)],
+                edges: Vec::new(),
+            })));
+        app
+    }
+
+    fn tower(mana: u8, owner: TeamId) -> Tower {
+        Tower {
+            mana,
+            node_id: 1,
+            owner: Some(owner),
+        }
+    }
+
+    /// Delivers a group of `owner` at node 1 and returns the tower afterwards
+    fn arrive(app: &mut App, tower: Entity, owner: TeamId, amount: u8) -> Tower {
+        app.world_mut().send_event(TroopGroupArrived {
+            group: TroopGroup::new(owner, amount, vec![2, 1]),
+            node_id: 1,
+        });
+        app.update();
+        app.world().get::<Tower>(tower).unwrap().clone()
+    }
+
+    fn captures(app: &App) -> Vec<TowerCaptured> {
+        app.world()
+            .resource::<Events<TowerCaptured>>()
+            .iter_current_update_events()
+            .cloned()
+            .collect()
+    }
+
+    /// A group on the edge 1-2 that has travelled `progress` from its start
+    fn group(app: &mut App, owner: TeamId, amount: u8, path: Vec<NodeId>, progress: f32) -> Entity {
+        let mut group = TroopGroup::new(owner, amount, path);
+        group.progress = progress;
+        app.world_mut().spawn(group).id()
+    }
+
+    fn amount(app: &App, group: Entity) -> Option<u8> {
+        app.world()
+            .get::<TroopGroup>(group)
+            .map(|group| group.amount)
+    }
+
+    #[test]
+    fn reinforcements_may_exceed_max_mana() {
+        let mut app = app();
+        let max_mana = TowerStats::new(1, &Balance::default()).max_mana();
+        let entity = app.world_mut().spawn(tower(max_mana - 5, 1)).id();
+
+        let reinforced = arrive(&mut app, entity, 1, 20);
+        assert_eq!(reinforced.mana, max_mana + 15);
+        assert_eq!(reinforced.owner, Some(1));
+        assert!(captures(&app).is_empty());
+    }
+
+    #[test]
+    fn reinforcements_saturate_at_the_mana_limit() {
+        let mut app = app();
+        let entity = app.world_mut().spawn(tower(250, 1)).id();
+
+        assert_eq!(arrive(&mut app, entity, 1, 10).mana, u8::MAX);
+    }
+
+    #[test]
+    fn outnumbering_the_garrison_by_one_captures_the_tower() {
+        let mut app = app();
+        let entity = app.world_mut().spawn(tower(10, 2)).id();
+
+        let captured = arrive(&mut app, entity, 1, 11);
+        assert_eq!(captured.owner, Some(1));
+        assert_eq!(captured.mana, 1);
+        assert_eq!(
+            captures(&app),
+            vec![TowerCaptured {
+                node_id: 1,
+                previous_owner: Some(2),
+                new_owner: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn matching_the_garrison_leaves_an_empty_tower() {
+        let mut app = app();
+        let entity = app.world_mut().spawn(tower(10, 2)).id();
+
+        let held = arrive(&mut app, entity, 1, 10);
+        assert_eq!(held.owner, Some(2));
+        assert_eq!(held.mana, 0);
+        assert!(captures(&app).is_empty());
+    }
+
+    #[test]
+    fn shielded_tower_is_not_captured() {
+        let mut app = app();
+        let entity = app
+            .world_mut()
+            .spawn((tower(10, 2), Shielded { remaining: 5.0 }))
+            .id();
+
+        let held = arrive(&mut app, entity, 1, 30);
+        assert_eq!(held.owner, Some(2));
+        assert_eq!(held.mana, 0);
+        assert!(captures(&app).is_empty());
+    }
+
+    #[test]
+    fn attacker_fights_the_front_most_defender_first() {
+        let mut app = app();
+        let attacker = group(&mut app, 1, 5, vec![1, 2], 70.0);
+        // Spawned rear-most first, so entity order alone would pick it
+        let rear = group(&mut app, 2, 3, vec![2, 1], 40.0);
+        let front = group(&mut app, 2, 3, vec![2, 1], 50.0);
+        app.update();
+
+        assert_eq!(amount(&app, attacker), None);
+        assert_eq!(amount(&app, front), None);
+        assert_eq!(amount(&app, rear), Some(1));
+
+        let losses: Vec<u8> = app
+            .world()
+            .resource::<Events<TroopGroupsClashed>>()
+            .iter_current_update_events()
+            .map(|clash| clash.losses)
+            .collect();
+        assert_eq!(losses, vec![3, 2]);
+    }
+
+    #[test]
+    fn defenders_level_with_each_other_fight_in_entity_order() {
+        let mut app = app();
+        let attacker = group(&mut app, 1, 5, vec![1, 2], 70.0);
+        let first = group(&mut app, 2, 3, vec![2, 1], 50.0);
+        let second = group(&mut app, 2, 3, vec![2, 1], 50.0);
+        app.update();
+
+        assert_eq!(amount(&app, attacker), None);
+        assert_eq!(amount(&app, first), None);
+        assert_eq!(amount(&app, second), Some(1));
+    }
+
+    #[test]
+    fn groups_that_have_not_met_do_not_fight() {
+        let mut app = app();
+        let attacker = group(&mut app, 1, 5, vec![1, 2], 30.0);
+        let defender = group(&mut app, 2, 3, vec![2, 1], 30.0);
+        app.update();
+
+        assert_eq!(amount(&app, attacker), Some(5));
+        assert_eq!(amount(&app, defender), Some(3));
+    }
+}
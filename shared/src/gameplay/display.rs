@@ -0,0 +1,45 @@
+use crate::gameplay::{map::CurrentMap, troops::TroopGroup};
+use bevy::prelude::*;
+use lightyear::prelude::*;
+
+/// World position of an interpolated troop group, updated every frame for the
+/// presentation layer.
+#[derive(Component, Clone, Copy, PartialEq, Debug, Default)]
+pub struct TroopPosition(pub Vec2);
+
+fn add_display_components(
+    mut commands: Commands,
+    q_troops: Query<Entity, (With<TroopGroup>, With<Interpolated>, Without<TroopPosition>)>,
+) {
+    for entity in q_troops.iter() {
+        commands.entity(entity).insert(TroopPosition::default());
+    }
+}
+
+fn update_troop_positions(
+    q_map: Query<&CurrentMap>,
+    mut q_troops: Query<(&TroopGroup, &mut TroopPosition), With<Interpolated>>,
+) {
+    let Ok(map) = q_map.single() else {
+        return;
+    };
+    for (group, mut position) in q_troops.iter_mut() {
+        if let Some(world) = group.position(&map.0) {
+            position.0 = world;
+        }
+    }
+}
+
+/// Client-side values for the presentation layer, derived from the
+/// interpolated copies of replicated entities. Does nothing on the server,
+/// which has no interpolated entities.
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (add_display_components, update_troop_positions).chain(),
+        );
+    }
+}
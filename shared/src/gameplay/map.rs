@@ -1,8 +1,9 @@
-use crate::gameplay::structures::StructureType;
+use crate::gameplay::structures::{NeutralGarrison, StructureType, TeamId};
+use crate::protocol::AppProtocolExt;
 use bevy::prelude::*;
-use lightyear::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 
 pub type NodeId = u16;
 
@@ -12,12 +13,74 @@ pub struct MapNode {
     pub connected_to: Vec<NodeId>,
     pub position: Vec2,
     pub node_type: NodeType,
+    /// Starting garrison and regen of a neutral tower. Only allowed on `Tower(None)`.
+    #[serde(default, skip_serializing_if = "NeutralGarrison::is_none")]
+    pub neutral: NeutralGarrison,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Map {
     pub name: String,
     pub nodes: HashMap<NodeId, MapNode>,
+    /// Attributes of every connection. `MapNode::connected_to` is kept in sync
+    /// with this list and lists where troops may travel from each node.
+    #[serde(default)]
+    pub edges: Vec<MapEdge>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Terrain {
+    #[default]
+    Road,
+    Swamp,
+    Bridge,
+}
+
+impl Terrain {
+    /// Factor applied to troop speed on edges of this terrain.
+    pub fn speed_multiplier(&self) -> f32 {
+        match self {
+            Terrain::Road => 1.0,
+            Terrain::Swamp => 0.5,
+            Terrain::Bridge => 1.25,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapEdge {
+    pub from: NodeId,
+    pub to: NodeId,
+    #[serde(default)]
+    pub terrain: Terrain,
+    /// Replaces the distance between the node positions as the edge length
+    #[serde(default)]
+    pub length: Option<f32>,
+    /// Only travel from `from` to `to` is allowed
+    #[serde(default)]
+    pub one_way: bool,
+}
+
+impl MapEdge {
+    pub const fn road(from: NodeId, to: NodeId) -> Self {
+        Self {
+            from,
+            to,
+            terrain: Terrain::Road,
+            length: None,
+            one_way: false,
+        }
+    }
+
+    /// Whether this edge joins `a` and `b`, ignoring direction.
+    pub fn joins(&self, a: NodeId, b: NodeId) -> bool {
+        (self.from == a && self.to == b) || (self.from == b && self.to == a)
+    }
+
+    /// Whether troops may travel along this edge from `from` to `to`.
+    pub fn allows(&self, from: NodeId, to: NodeId) -> bool {
+        (self.from == from && self.to == to) || (!self.one_way && self.from == to && self.to == from)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -26,16 +89,30 @@ pub enum NodeType {
     Waypoint,
 }
 
+/// On-disk map format (`*.map.ron`). Nodes are stored as a list so files read
+/// naturally and keep a stable order. `edges` only needs to list connections
+/// that are not plain two-way roads, so files without it still load.
+#[derive(Asset, TypePath, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapFile {
+    pub name: String,
+    pub nodes: Vec<MapNode>,
+    #[serde(default)]
+    pub edges: Vec<MapEdge>,
+}
+
 pub struct MapNodeData {
     pub id: NodeId,
     pub connected_to: &'static [NodeId],
     pub position: Vec2,
     pub node_type: NodeType,
+    pub neutral: NeutralGarrison,
 }
 
 pub struct MapData {
     pub name: &'static str,
     pub nodes: &'static [MapNodeData],
+    /// Edges that are not plain two-way roads
+    pub edges: &'static [MapEdge],
 }
 
 impl Map {
@@ -49,21 +126,104 @@ impl Map {
                     connected_to: node_data.connected_to.to_vec(),
                     position: node_data.position,
                     node_type: node_data.node_type.clone(),
+                    neutral: node_data.neutral,
                 };
                 (node_data.id, node)
             })
             .collect();
 
-        let map = Self {
+        let mut map = Self {
             name: map_data.name.to_string(),
             nodes,
+            edges: map_data.edges.to_vec(),
         };
+        map.sync_edges();
+
+        if let Err(errors) = map.validate() {
+            panic!(
+                "Invalid map '{}':\n{}",
+                map.name,
+                format_validation_errors(&errors)
+            );
+        }
+        map
+    }
 
-        map.validate_undirected()
-            .expect("Map must be an undirected graph");
+    pub fn from_file(map_file: MapFile) -> Result<Self, Vec<MapValidationError>> {
+        let map = Self::from_file_unchecked(map_file);
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// Builds a map without validating it, for tools that edit maps in steps.
+    pub fn from_file_unchecked(map_file: MapFile) -> Self {
+        let mut map = Self {
+            name: map_file.name,
+            nodes: map_file
+                .nodes
+                .into_iter()
+                .map(|node| (node.id, node))
+                .collect(),
+            edges: map_file.edges,
+        };
+        map.sync_edges();
         map
     }
 
+    pub fn to_file(&self) -> MapFile {
+        let mut nodes: Vec<MapNode> = self.nodes.values().cloned().collect();
+        nodes.sort_by_key(|node| node.id);
+        let edges = self
+            .edges
+            .iter()
+            .filter(|edge| **edge != MapEdge::road(edge.from, edge.to))
+            .cloned()
+            .collect();
+        MapFile {
+            name: self.name.clone(),
+            nodes,
+            edges,
+        }
+    }
+
+    /// Brings `edges` and `connected_to` in line: connections only listed in
+    /// `connected_to` get a default road edge, and explicit edges are added to
+    /// the `connected_to` lists of the nodes they can be travelled from. Never
+    /// links the far end of a one-way edge back; if a node already lists it,
+    /// `validate` reports a `WrongWayEdge`.
+    pub fn sync_edges(&mut self) {
+        let explicit = self.edges.clone();
+
+        let mut ids: Vec<NodeId> = self.nodes.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            for &to in &self.nodes[&id].connected_to {
+                let listed = self.edges.iter().any(|edge| edge.joins(id, to));
+                if !listed && self.nodes.contains_key(&to) {
+                    self.edges.push(MapEdge::road(id, to));
+                }
+            }
+        }
+
+        for edge in explicit {
+            let mut link = |from: NodeId, to: NodeId| {
+                let node = self.nodes.get_mut(&from);
+                if let Some(node) = node.filter(|node| !node.connected_to.contains(&to)) {
+                    node.connected_to.push(to);
+                }
+            };
+            link(edge.from, edge.to);
+            if !edge.one_way {
+                link(edge.to, edge.from);
+            }
+        }
+    }
+
+    /// The edge troops would use to travel from `from` to `to`.
+    pub fn edge(&self, from: NodeId, to: NodeId) -> Option<&MapEdge> {
+        self.edges.iter().find(|edge| edge.allows(from, to))
+    }
+
     pub fn get_node(&self, id: NodeId) -> Option<&MapNode> {
         self.nodes.get(&id)
     }
@@ -77,26 +237,308 @@ impl Map {
         })
     }
 
-    fn validate_undirected(&self) -> Result<(), String> {
-        for (node_id, node) in &self.nodes {
+    pub fn are_connected(&self, from: NodeId, to: NodeId) -> bool {
+        self.get_node(from)
+            .is_some_and(|node| node.connected_to.contains(&to))
+    }
+
+    /// Length of the edge between two connected nodes: its length override, or
+    /// else the distance between their positions.
+    pub fn edge_length(&self, from: NodeId, to: NodeId) -> Option<f32> {
+        if !self.are_connected(from, to) {
+            return None;
+        }
+        if let Some(length) = self.edge(from, to).and_then(|edge| edge.length) {
+            return Some(length);
+        }
+        let from_node = self.get_node(from)?;
+        let to_node = self.get_node(to)?;
+        Some(from_node.position.distance(to_node.position))
+    }
+
+    /// Troop speed multiplier on the edge from `from` to `to`.
+    pub fn edge_speed_multiplier(&self, from: NodeId, to: NodeId) -> f32 {
+        self.edge(from, to)
+            .map_or(1.0, |edge| edge.terrain.speed_multiplier())
+    }
+
+    /// Edge length divided by its speed multiplier, i.e. the distance a troop
+    /// group would cover on open road in the same time. Used as routing weight.
+    pub fn edge_cost(&self, from: NodeId, to: NodeId) -> Option<f32> {
+        Some(self.edge_length(from, to)? / self.edge_speed_multiplier(from, to))
+    }
+
+    /// Runs every map check and returns all problems found, in node id order.
+    pub fn validate(&self) -> Result<(), Vec<MapValidationError>> {
+        let mut errors = Vec::new();
+        let mut ids: Vec<NodeId> = self.nodes.keys().copied().collect();
+        ids.sort_unstable();
+
+        for &key in &ids {
+            let node = &self.nodes[&key];
+            if node.id != key {
+                errors.push(MapValidationError::IdMismatch {
+                    key,
+                    node_id: node.id,
+                });
+            }
+
+            if !node.position.is_finite() {
+                errors.push(MapValidationError::NonFinitePosition { node: key });
+            }
+
+            let neutral_tower = matches!(
+                node.node_type,
+                NodeType::StructureType(StructureType::Tower(None))
+            );
+            if !neutral_tower && !node.neutral.is_none() {
+                errors.push(MapValidationError::MisplacedGarrison { node: key });
+            }
+
+            let mut seen = HashSet::new();
             for &connected_id in &node.connected_to {
-                if let Some(connected_node) = self.nodes.get(&connected_id) {
-                    if !connected_node.connected_to.contains(node_id) {
-                        return Err(format!(
-                            "Graph is not undirected: node {} connects to {} but {} doesn't connect back",
-                            node_id, connected_id, connected_id
-                        ));
+                if connected_id == key {
+                    errors.push(MapValidationError::SelfLoop { node: key });
+                    continue;
+                }
+                if !seen.insert(connected_id) {
+                    errors.push(MapValidationError::DuplicateEdge {
+                        from: key,
+                        to: connected_id,
+                    });
+                    continue;
+                }
+                let wrong_way = self.edges.iter().any(|edge| {
+                    edge.one_way && edge.from == connected_id && edge.to == key
+                });
+                if wrong_way {
+                    errors.push(MapValidationError::WrongWayEdge {
+                        from: key,
+                        to: connected_id,
+                    });
+                    continue;
+                }
+                let one_way = self.edge(key, connected_id).is_some_and(|edge| edge.one_way);
+                match self.nodes.get(&connected_id) {
+                    Some(connected_node)
+                        if !one_way && !connected_node.connected_to.contains(&key) =>
+                    {
+                        errors.push(MapValidationError::NotUndirected {
+                            from: key,
+                            to: connected_id,
+                        });
                     }
-                } else {
-                    return Err(format!(
-                        "Node {} references non-existent node {}",
-                        node_id, connected_id
-                    ));
+                    Some(_) => {}
+                    None => errors.push(MapValidationError::DanglingReference {
+                        from: key,
+                        to: connected_id,
+                    }),
+                }
+            }
+        }
+
+        for (i, &a) in ids.iter().enumerate() {
+            for &b in &ids[i + 1..] {
+                if self.nodes[&a].position.distance(self.nodes[&b].position) < f32::EPSILON {
+                    errors.push(MapValidationError::OverlappingPositions { a, b });
                 }
             }
         }
-        Ok(())
+
+        for (index, edge) in self.edges.iter().enumerate() {
+            for endpoint in [edge.from, edge.to] {
+                if !self.nodes.contains_key(&endpoint) {
+                    errors.push(MapValidationError::DanglingReference {
+                        from: if endpoint == edge.from { edge.to } else { edge.from },
+                        to: endpoint,
+                    });
+                }
+            }
+            if self.edges[..index]
+                .iter()
+                .any(|other| other.joins(edge.from, edge.to))
+            {
+                errors.push(MapValidationError::DuplicateEdge {
+                    from: edge.from,
+                    to: edge.to,
+                });
+            }
+            if edge.length.is_some_and(|length| length <= 0.0 || !length.is_finite()) {
+                errors.push(MapValidationError::InvalidEdgeLength {
+                    from: edge.from,
+                    to: edge.to,
+                });
+            }
+        }
+
+        let components = self.connected_components();
+        if components.len() > 1 {
+            errors.push(MapValidationError::Disconnected { components });
+        }
+
+        errors.extend(self.validate_base_towers());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
+
+    /// Groups of nodes reachable from each other, each sorted, ordered by lowest id.
+    /// Edges are followed in both directions.
+    pub fn connected_components(&self) -> Vec<Vec<NodeId>> {
+        let mut ids: Vec<NodeId> = self.nodes.keys().copied().collect();
+        ids.sort_unstable();
+
+        let mut neighbours: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for node in self.nodes.values() {
+            for &connected_id in &node.connected_to {
+                if self.nodes.contains_key(&connected_id) {
+                    neighbours.entry(node.id).or_default().push(connected_id);
+                    neighbours.entry(connected_id).or_default().push(node.id);
+                }
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut components = Vec::new();
+        for id in ids {
+            if !visited.insert(id) {
+                continue;
+            }
+            let mut component = vec![id];
+            let mut stack = vec![id];
+            while let Some(current) = stack.pop() {
+                for &next in neighbours.get(&current).into_iter().flatten() {
+                    if visited.insert(next) {
+                        component.push(next);
+                        stack.push(next);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    fn validate_base_towers(&self) -> Vec<MapValidationError> {
+        let mut bases: BTreeMap<TeamId, Vec<NodeId>> = BTreeMap::new();
+        for node in self.nodes.values() {
+            match node.node_type {
+                NodeType::StructureType(StructureType::BaseTower(team)) => {
+                    bases.entry(team).or_default().push(node.id);
+                }
+                NodeType::StructureType(StructureType::Tower(Some(team))) => {
+                    bases.entry(team).or_default();
+                }
+                _ => {}
+            }
+        }
+
+        bases
+            .into_iter()
+            .filter_map(|(team, mut nodes)| match nodes.len() {
+                0 => Some(MapValidationError::MissingBaseTower { team }),
+                1 => None,
+                _ => {
+                    nodes.sort_unstable();
+                    Some(MapValidationError::MultipleBaseTowers { team, nodes })
+                }
+            })
+            .collect()
+    }
+}
+
+/// A single problem found by `Map::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapValidationError {
+    /// `from` lists `to` as a neighbour but not the other way around
+    NotUndirected { from: NodeId, to: NodeId },
+    /// `from` lists a neighbour that is not in the map
+    DanglingReference { from: NodeId, to: NodeId },
+    SelfLoop { node: NodeId },
+    /// `from` lists `to` more than once
+    DuplicateEdge { from: NodeId, to: NodeId },
+    /// The graph falls apart into several groups of nodes
+    Disconnected { components: Vec<Vec<NodeId>> },
+    OverlappingPositions { a: NodeId, b: NodeId },
+    /// A team owns towers on the map but has no base tower
+    MissingBaseTower { team: TeamId },
+    MultipleBaseTowers { team: TeamId, nodes: Vec<NodeId> },
+    /// The node is stored under a different id than its own `id` field
+    IdMismatch { key: NodeId, node_id: NodeId },
+    /// The edge's length override is zero, negative or not a number
+    InvalidEdgeLength { from: NodeId, to: NodeId },
+    /// Neutral garrison settings on a node that is not a neutral tower
+    MisplacedGarrison { node: NodeId },
+    /// `from` lists `to` as a neighbour, but the edge between them is one-way from `to` to `from`
+    WrongWayEdge { from: NodeId, to: NodeId },
+    /// The node's position is infinite or not a number
+    NonFinitePosition { node: NodeId },
+}
+
+impl fmt::Display for MapValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotUndirected { from, to } => write!(
+                f,
+                "Graph is not undirected: node {} connects to {} but {} doesn't connect back",
+                from, to, to
+            ),
+            Self::DanglingReference { from, to } => {
+                write!(f, "Node {} references non-existent node {}", from, to)
+            }
+            Self::SelfLoop { node } => write!(f, "Node {} is connected to itself", node),
+            Self::DuplicateEdge { from, to } => {
+                write!(f, "Node {} lists node {} more than once", from, to)
+            }
+            Self::Disconnected { components } => write!(
+                f,
+                "Map is split into {} disconnected parts: {:?}",
+                components.len(),
+                components
+            ),
+            Self::OverlappingPositions { a, b } => {
+                write!(f, "Nodes {} and {} share the same position", a, b)
+            }
+            Self::MissingBaseTower { team } => write!(f, "Team {} has no base tower", team),
+            Self::MultipleBaseTowers { team, nodes } => {
+                write!(f, "Team {} has several base towers: {:?}", team, nodes)
+            }
+            Self::IdMismatch { key, node_id } => {
+                write!(f, "Node stored under id {} has id {}", key, node_id)
+            }
+            Self::InvalidEdgeLength { from, to } => {
+                write!(f, "Edge {} - {} has an invalid length override", from, to)
+            }
+            Self::MisplacedGarrison { node } => write!(
+                f,
+                "Node {} has a neutral garrison but is not a neutral tower",
+                node
+            ),
+            Self::WrongWayEdge { from, to } => write!(
+                f,
+                "Node {} connects to {}, but the edge between them is one-way from {} to {}",
+                from, to, to, from
+            ),
+            Self::NonFinitePosition { node } => {
+                write!(f, "Node {} has an infinite or NaN position", node)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapValidationError {}
+
+/// Joins validation errors into one line each, for logs and panics.
+pub fn format_validation_errors(errors: &[impl fmt::Display]) -> String {
+    errors
+        .iter()
+        .map(|error| format!("  - {}", error))
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 pub const EXAMPLE_MAP: MapData = MapData {
@@ -107,41 +549,213 @@ pub const EXAMPLE_MAP: MapData = MapData {
             connected_to: &[2, 3],
             position: Vec2::new(0.0, 0.0),
             node_type: NodeType::StructureType(StructureType::Tower(None)),
+            neutral: NeutralGarrison {
+                garrison: 10,
+                regen_rate: 1,
+                regen_cap: 15,
+                raid_below: None,
+            },
         },
         MapNodeData {
             id: 2,
             connected_to: &[1, 3, 4],
             position: Vec2::new(100.0, 0.0),
             node_type: NodeType::Waypoint,
+            neutral: NeutralGarrison::NONE,
         },
         MapNodeData {
             id: 3,
             connected_to: &[1, 2, 4],
             position: Vec2::new(50.0, 86.6),
             node_type: NodeType::StructureType(StructureType::BaseTower(1)),
+            neutral: NeutralGarrison::NONE,
         },
         MapNodeData {
             id: 4,
             connected_to: &[2, 3],
             position: Vec2::new(150.0, 86.6),
-            node_type: NodeType::StructureType(StructureType::Tower(Some(2))),
+            node_type: NodeType::StructureType(StructureType::BaseTower(2)),
+            neutral: NeutralGarrison::NONE,
         },
     ],
+    edges: &[],
 };
 
+/// The map of this match. Spawned and replicated by the server.
 #[derive(Component, Serialize, Deserialize, PartialEq)]
 pub struct CurrentMap(pub Map);
 
-fn setup_current_map(mut commands: Commands) {
-    let default_map = Map::from_const(&EXAMPLE_MAP);
-    commands.spawn(CurrentMap(default_map));
-}
-
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_current_map)
-            .register_component::<CurrentMap>();
+        app.register_replicated::<CurrentMap>("CurrentMap");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: NodeId, x: f32, connected_to: &[NodeId]) -> MapNode {
+        MapNode {
+            id,
+            connected_to: connected_to.to_vec(),
+            position: Vec2::new(x, 0.0),
+            node_type: NodeType::Waypoint,
+            neutral: NeutralGarrison::NONE,
+        }
+    }
+
+    fn typed(node: MapNode, structure: StructureType) -> MapNode {
+        MapNode {
+            node_type: NodeType::StructureType(structure),
+            ..node
+        }
+    }
+
+    /// Errors of a map built like a loaded file, so `sync_edges` has run
+    fn errors(nodes: Vec<MapNode>, edges: Vec<MapEdge>) -> Vec<MapValidationError> {
+        let map = Map::from_file_unchecked(MapFile {
+            name: "Test".to_string(),
+            nodes,
+            edges,
+        });
+        map.validate().err().unwrap_or_default()
+    }
+
+    #[test]
+    fn example_map_is_valid() {
+        assert_eq!(Map::from_const(&EXAMPLE_MAP).validate(), Ok(()));
+    }
+
+    #[test]
+    fn not_undirected() {
+        let errors = errors(vec![node(1, 0.0, &[2]), node(2, 100.0, &[])], Vec::new());
+        assert!(errors.contains(&MapValidationError::NotUndirected { from: 1, to: 2 }));
+    }
+
+    #[test]
+    fn dangling_reference() {
+        let errors = errors(vec![node(1, 0.0, &[2, 9]), node(2, 100.0, &[1])], Vec::new());
+        assert!(errors.contains(&MapValidationError::DanglingReference { from: 1, to: 9 }));
+    }
+
+    #[test]
+    fn self_loop() {
+        let errors = errors(vec![node(1, 0.0, &[1, 2]), node(2, 100.0, &[1])], Vec::new());
+        assert!(errors.contains(&MapValidationError::SelfLoop { node: 1 }));
+    }
+
+    #[test]
+    fn duplicate_edge() {
+        let errors = errors(vec![node(1, 0.0, &[2, 2]), node(2, 100.0, &[1])], Vec::new());
+        assert!(errors.contains(&MapValidationError::DuplicateEdge { from: 1, to: 2 }));
+    }
+
+    #[test]
+    fn disconnected() {
+        let errors = errors(
+            vec![node(1, 0.0, &[2]), node(2, 100.0, &[1]), node(3, 200.0, &[])],
+            Vec::new(),
+        );
+        assert!(errors.contains(&MapValidationError::Disconnected {
+            components: vec![vec![1, 2], vec![3]],
+        }));
+    }
+
+    #[test]
+    fn overlapping_positions() {
+        let errors = errors(vec![node(1, 0.0, &[2]), node(2, 0.0, &[1])], Vec::new());
+        assert!(errors.contains(&MapValidationError::OverlappingPositions { a: 1, b: 2 }));
+    }
+
+    #[test]
+    fn missing_base_tower() {
+        let errors = errors(
+            vec![
+                typed(node(1, 0.0, &[2]), StructureType::Tower(Some(1))),
+                node(2, 100.0, &[1]),
+            ],
+            Vec::new(),
+        );
+        assert!(errors.contains(&MapValidationError::MissingBaseTower { team: 1 }));
+    }
+
+    #[test]
+    fn multiple_base_towers() {
+        let errors = errors(
+            vec![
+                typed(node(1, 0.0, &[2]), StructureType::BaseTower(1)),
+                typed(node(2, 100.0, &[1]), StructureType::BaseTower(1)),
+            ],
+            Vec::new(),
+        );
+        assert!(errors.contains(&MapValidationError::MultipleBaseTowers {
+            team: 1,
+            nodes: vec![1, 2],
+        }));
+    }
+
+    #[test]
+    fn id_mismatch() {
+        let map = Map {
+            name: "Test".to_string(),
+            nodes: HashMap::from([(1, node(5, 0.0, &[]))]),
+            edges: Vec::new(),
+        };
+        let errors = map.validate().err().unwrap_or_default();
+        assert!(errors.contains(&MapValidationError::IdMismatch { key: 1, node_id: 5 }));
+    }
+
+    #[test]
+    fn invalid_edge_length() {
+        let errors = errors(
+            vec![node(1, 0.0, &[2]), node(2, 100.0, &[1])],
+            vec![MapEdge {
+                length: Some(-10.0),
+                ..MapEdge::road(1, 2)
+            }],
+        );
+        assert!(errors.contains(&MapValidationError::InvalidEdgeLength { from: 1, to: 2 }));
+    }
+
+    #[test]
+    fn misplaced_garrison() {
+        let garrisoned = MapNode {
+            neutral: NeutralGarrison {
+                garrison: 5,
+                ..NeutralGarrison::NONE
+            },
+            ..node(1, 0.0, &[2])
+        };
+        let errors = errors(vec![garrisoned, node(2, 100.0, &[1])], Vec::new());
+        assert!(errors.contains(&MapValidationError::MisplacedGarrison { node: 1 }));
+    }
+
+    #[test]
+    fn wrong_way_edge() {
+        let one_way = MapEdge {
+            one_way: true,
+            ..MapEdge::road(1, 2)
+        };
+        let errors = errors(vec![node(1, 0.0, &[2]), node(2, 100.0, &[1])], vec![one_way]);
+        assert_eq!(errors, vec![MapValidationError::WrongWayEdge { from: 2, to: 1 }]);
+    }
+
+    #[test]
+    fn one_way_edge_is_valid() {
+        let one_way = MapEdge {
+            one_way: true,
+            ..MapEdge::road(1, 2)
+        };
+        let errors = errors(vec![node(1, 0.0, &[]), node(2, 100.0, &[])], vec![one_way]);
+        assert_eq!(errors, Vec::new());
+    }
+
+    #[test]
+    fn non_finite_position() {
+        let errors = errors(vec![node(1, 0.0, &[2]), node(2, f32::NAN, &[1])], Vec::new());
+        assert!(errors.contains(&MapValidationError::NonFinitePosition { node: 2 }));
     }
 }
@@ -0,0 +1,418 @@
+use crate::gameplay::{
+    map::{Map, MapEdge, NodeId, NodeType},
+    structures::{StructureType, TeamId},
+};
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::f32::consts::TAU;
+use std::fmt;
+
+/// Two positions closer than this are treated as the same spot.
+const POSITION_TOLERANCE: f32 = 1.0;
+
+/// A neutral tower is contested when the closest bases are within this fraction
+/// of each other's distance.
+const CONTESTED_TOLERANCE: f32 = 0.05;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapSymmetry {
+    /// Bases mirror each other across the line through `origin` along `axis`
+    Mirror { origin: Vec2, axis: Vec2 },
+    /// Rotating by `360° / order` around `center` maps every base onto another base
+    Rotational { center: Vec2, order: usize },
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeamMetrics {
+    pub team: TeamId,
+    pub base: NodeId,
+    /// Graph distance from the base to every reachable neutral tower
+    pub distances_to_neutral: BTreeMap<NodeId, f32>,
+    /// Neutral towers this team reaches strictly before every other team
+    pub towers_reached_first: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FairnessReport {
+    pub map_name: String,
+    pub symmetry: MapSymmetry,
+    /// Whether the symmetry of the bases also maps every node and edge onto an
+    /// equivalent node and edge
+    pub graph_symmetric: bool,
+    pub teams: Vec<TeamMetrics>,
+    /// Neutral towers that two or more teams reach at about the same distance
+    pub contested: Vec<NodeId>,
+}
+
+impl FairnessReport {
+    /// A map is considered fair when it is fully symmetric and every team reaches
+    /// the same number of neutral towers first.
+    pub fn is_fair(&self) -> bool {
+        let first_counts_equal = self
+            .teams
+            .windows(2)
+            .all(|pair| pair[0].towers_reached_first == pair[1].towers_reached_first);
+        self.symmetry != MapSymmetry::None && self.graph_symmetric && first_counts_equal
+    }
+}
+
+impl fmt::Display for FairnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Fairness report for '{}'", self.map_name)?;
+        match &self.symmetry {
+            MapSymmetry::Mirror { origin, axis } => writeln!(
+                f,
+                "  symmetry: mirror through ({:.1}, {:.1}) along ({:.2}, {:.2})",
+                origin.x, origin.y, axis.x, axis.y
+            )?,
+            MapSymmetry::Rotational { center, order } => writeln!(
+                f,
+                "  symmetry: {}-fold rotation around ({:.1}, {:.1})",
+                order, center.x, center.y
+            )?,
+            MapSymmetry::None => writeln!(f, "  symmetry: none")?,
+        }
+        writeln!(f, "  graph symmetric: {}", self.graph_symmetric)?;
+        for team in &self.teams {
+            writeln!(
+                f,
+                "  team {} (base {}): reaches {} neutral towers first",
+                team.team, team.base, team.towers_reached_first
+            )?;
+            for (node, distance) in &team.distances_to_neutral {
+                writeln!(f, "    tower {}: {:.1}", node, distance)?;
+            }
+        }
+        writeln!(f, "  contested: {:?}", self.contested)?;
+        write!(f, "  fair: {}", self.is_fair())
+    }
+}
+
+impl Map {
+    pub fn analyze_fairness(&self) -> FairnessReport {
+        let bases = self.base_towers();
+        let neutrals: Vec<NodeId> = self
+            .sorted_node_ids()
+            .into_iter()
+            .filter(|id| {
+                self.nodes[id].node_type == NodeType::StructureType(StructureType::Tower(None))
+            })
+            .collect();
+
+        let mut teams: Vec<TeamMetrics> = bases
+            .iter()
+            .map(|&(team, base)| {
+                let tree = self.shortest_paths_from(base);
+                TeamMetrics {
+                    team,
+                    base,
+                    distances_to_neutral: neutrals
+                        .iter()
+                        .filter_map(|&id| tree.distance_to(id).map(|distance| (id, distance)))
+                        .collect(),
+                    towers_reached_first: 0,
+                }
+            })
+            .collect();
+
+        let mut contested = Vec::new();
+        for &tower in &neutrals {
+            let mut reach: Vec<(usize, f32)> = teams
+                .iter()
+                .enumerate()
+                .filter_map(|(i, team)| team.distances_to_neutral.get(&tower).map(|&d| (i, d)))
+                .collect();
+            reach.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+            match reach.as_slice() {
+                [] => {}
+                [(only, _)] => teams[*only].towers_reached_first += 1,
+                [(first, best), (_, second), ..] => {
+                    if *second - *best <= second * CONTESTED_TOLERANCE {
+                        contested.push(tower);
+                    } else {
+                        teams[*first].towers_reached_first += 1;
+                    }
+                }
+            }
+        }
+
+        let (symmetry, graph_symmetric) = self.detect_symmetry(&bases);
+
+        FairnessReport {
+            map_name: self.name.clone(),
+            symmetry,
+            graph_symmetric,
+            teams,
+            contested,
+        }
+    }
+
+    /// Base towers as `(team, node)`, ordered by team.
+    pub fn base_towers(&self) -> Vec<(TeamId, NodeId)> {
+        let mut bases: Vec<(TeamId, NodeId)> = self
+            .nodes
+            .values()
+            .filter_map(|node| match node.node_type {
+                NodeType::StructureType(StructureType::BaseTower(team)) => Some((team, node.id)),
+                _ => None,
+            })
+            .collect();
+        bases.sort_unstable();
+        bases
+    }
+
+    fn sorted_node_ids(&self) -> Vec<NodeId> {
+        let mut ids: Vec<NodeId> = self.nodes.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Finds a transform that maps every base onto another base. When several
+    /// fit, one that also maps the whole graph onto itself is preferred.
+    fn detect_symmetry(&self, bases: &[(TeamId, NodeId)]) -> (MapSymmetry, bool) {
+        let positions: Vec<Vec2> = bases
+            .iter()
+            .map(|(_, id)| self.nodes[id].position)
+            .collect();
+        if positions.len() < 2 {
+            return (MapSymmetry::None, false);
+        }
+
+        let mut candidates = Vec::new();
+
+        let center = positions.iter().sum::<Vec2>() / positions.len() as f32;
+        let order = positions.len();
+        let rotated_onto_bases = positions.iter().all(|&position| {
+            let rotated = rotate_around(position, center, TAU / order as f32);
+            positions
+                .iter()
+                .any(|&other| other.distance(rotated) < POSITION_TOLERANCE)
+        });
+        if rotated_onto_bases {
+            candidates.push(MapSymmetry::Rotational { center, order });
+        }
+
+        if let [a, b] = positions[..] {
+            // Mirror across the perpendicular bisector of the two bases
+            let axis = (b - a).perp().normalize_or_zero();
+            if axis != Vec2::ZERO {
+                candidates.push(MapSymmetry::Mirror {
+                    origin: (a + b) / 2.0,
+                    axis,
+                });
+            }
+        }
+
+        let graph_symmetric = candidates
+            .iter()
+            .position(|candidate| self.is_graph_symmetric(candidate));
+        match graph_symmetric {
+            Some(index) => (candidates.swap_remove(index), true),
+            None => (
+                candidates.into_iter().next().unwrap_or(MapSymmetry::None),
+                false,
+            ),
+        }
+    }
+
+    fn is_graph_symmetric(&self, symmetry: &MapSymmetry) -> bool {
+        let transform = |position: Vec2| match *symmetry {
+            MapSymmetry::Mirror { origin, axis } => reflect_across(position, origin, axis),
+            MapSymmetry::Rotational { center, order } => {
+                rotate_around(position, center, TAU / order as f32)
+            }
+            MapSymmetry::None => position,
+        };
+
+        // Map every node to the node sitting at its transformed position
+        let mut image: BTreeMap<NodeId, NodeId> = BTreeMap::new();
+        for id in self.sorted_node_ids() {
+            let node = &self.nodes[&id];
+            let target = transform(node.position);
+            let Some(partner) = self
+                .nodes
+                .values()
+                .find(|other| other.position.distance(target) < POSITION_TOLERANCE)
+            else {
+                return false;
+            };
+            if node_kind(&node.node_type) != node_kind(&partner.node_type)
+                || node.neutral != partner.neutral
+            {
+                return false;
+            }
+            image.insert(id, partner.id);
+        }
+
+        self.nodes.values().all(|node| {
+            node.connected_to.iter().all(|&to| {
+                let (Some(&from_image), Some(&to_image)) = (image.get(&node.id), image.get(&to))
+                else {
+                    return false;
+                };
+                // Everything but the endpoints has to match
+                let attributes = |edge: &MapEdge| (edge.terrain, edge.length, edge.one_way);
+                let same_edge = self.edge(node.id, to).map(attributes)
+                    == self.edge(from_image, to_image).map(attributes);
+                self.are_connected(from_image, to_image) && same_edge
+            })
+        })
+    }
+}
+
+/// Node type with team ownership stripped, so symmetric nodes of different
+/// teams compare equal.
+fn node_kind(node_type: &NodeType) -> u8 {
+    match node_type {
+        NodeType::Waypoint => 0,
+        NodeType::StructureType(StructureType::Tower(None)) => 1,
+        NodeType::StructureType(StructureType::Tower(Some(_))) => 2,
+        NodeType::StructureType(StructureType::BaseTower(_)) => 3,
+    }
+}
+
+fn rotate_around(position: Vec2, center: Vec2, angle: f32) -> Vec2 {
+    center + Vec2::from_angle(angle).rotate(position - center)
+}
+
+fn reflect_across(position: Vec2, origin: Vec2, axis: Vec2) -> Vec2 {
+    let offset = position - origin;
+    origin + 2.0 * offset.project_onto_normalized(axis) - offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::map::{MapFile, MapNode, Terrain};
+    use crate::gameplay::structures::NeutralGarrison;
+
+    const NEUTRAL: NodeType = NodeType::StructureType(StructureType::Tower(None));
+
+    fn base(team: TeamId) -> NodeType {
+        NodeType::StructureType(StructureType::BaseTower(team))
+    }
+
+    fn node(id: NodeId, position: Vec2, node_type: NodeType, connected_to: &[NodeId]) -> MapNode {
+        MapNode {
+            id,
+            connected_to: connected_to.to_vec(),
+            position,
+            node_type,
+            neutral: NeutralGarrison::NONE,
+        }
+    }
+
+    fn map(nodes: Vec<MapNode>, edges: Vec<MapEdge>) -> Map {
+        Map::from_file_unchecked(MapFile {
+            name: "Test".to_string(),
+            nodes,
+            edges,
+        })
+    }
+
+    /// Two bases with a neutral tower above the middle, joined to both
+    fn mirrored(edges: Vec<MapEdge>) -> Map {
+        map(
+            vec![
+                node(1, Vec2::new(0.0, 0.0), base(1), &[3]),
+                node(2, Vec2::new(200.0, 0.0), base(2), &[3]),
+                node(3, Vec2::new(100.0, 100.0), NEUTRAL, &[1, 2]),
+            ],
+            edges,
+        )
+    }
+
+    #[test]
+    fn mirrored_map_is_graph_symmetric() {
+        let report = mirrored(Vec::new()).analyze_fairness();
+        assert!(matches!(report.symmetry, MapSymmetry::Mirror { .. }));
+        assert!(report.graph_symmetric);
+        assert!(report.is_fair());
+    }
+
+    #[test]
+    fn mirror_with_different_edge_lengths_is_not_graph_symmetric() {
+        let mut long_edge = MapEdge::road(1, 3);
+        long_edge.length = Some(500.0);
+        let report = mirrored(vec![long_edge]).analyze_fairness();
+        assert!(!report.graph_symmetric);
+        assert!(!report.is_fair());
+    }
+
+    #[test]
+    fn mirror_with_one_one_way_edge_is_not_graph_symmetric() {
+        let mut one_way = MapEdge::road(3, 1);
+        one_way.one_way = true;
+        let mut nodes = mirrored(Vec::new()).nodes;
+        nodes.get_mut(&1).unwrap().connected_to.clear();
+        let report = map(nodes.into_values().collect(), vec![one_way]).analyze_fairness();
+        assert!(!report.graph_symmetric);
+    }
+
+    #[test]
+    fn mirror_with_different_terrain_is_not_graph_symmetric() {
+        let mut swamp = MapEdge::road(2, 3);
+        swamp.terrain = Terrain::Swamp;
+        let report = mirrored(vec![swamp]).analyze_fairness();
+        assert!(!report.graph_symmetric);
+    }
+
+    #[test]
+    fn three_bases_around_a_center_are_rotationally_symmetric() {
+        let corner = |degrees: f32| Vec2::from_angle(degrees.to_radians()) * 100.0;
+        let report = map(
+            vec![
+                node(1, corner(90.0), base(1), &[4]),
+                node(2, corner(210.0), base(2), &[4]),
+                node(3, corner(330.0), base(3), &[4]),
+                node(4, Vec2::ZERO, NEUTRAL, &[1, 2, 3]),
+            ],
+            Vec::new(),
+        )
+        .analyze_fairness();
+        assert!(matches!(
+            report.symmetry,
+            MapSymmetry::Rotational { order: 3, .. }
+        ));
+        assert!(report.graph_symmetric);
+        assert_eq!(report.contested, vec![4]);
+    }
+
+    #[test]
+    fn closer_team_reaches_a_tower_first() {
+        // 1 -- 3 ---- 4 ---- 2, tower 3 is closer to team 1, tower 4 halfway
+        let report = map(
+            vec![
+                node(1, Vec2::new(0.0, 0.0), base(1), &[3]),
+                node(3, Vec2::new(100.0, 0.0), NEUTRAL, &[1, 4]),
+                node(4, Vec2::new(200.0, 0.0), NEUTRAL, &[3, 2]),
+                node(2, Vec2::new(400.0, 0.0), base(2), &[4]),
+            ],
+            Vec::new(),
+        )
+        .analyze_fairness();
+
+        let first: Vec<(TeamId, usize)> = report
+            .teams
+            .iter()
+            .map(|team| (team.team, team.towers_reached_first))
+            .collect();
+        assert_eq!(first, vec![(1, 1), (2, 0)]);
+        assert_eq!(report.contested, vec![4]);
+        assert!(!report.is_fair());
+    }
+
+    #[test]
+    fn towers_within_the_tolerance_are_contested() {
+        let report = mirrored(Vec::new()).analyze_fairness();
+        assert_eq!(report.contested, vec![3]);
+        assert!(
+            report
+                .teams
+                .iter()
+                .all(|team| team.towers_reached_first == 0)
+        );
+    }
+}
@@ -0,0 +1,322 @@
+use crate::gameplay::{
+    map::{Map, MapNode, MapValidationError, NodeId, NodeType},
+    structures::{NeutralGarrison, StructureType, TeamId},
+};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::fmt;
+
+/// Nodes of the same sector are kept at least this far apart.
+const MIN_NODE_SPACING: f32 = 60.0;
+/// Attempts at placing a node before the generator gives up on it.
+const PLACEMENT_ATTEMPTS: usize = 64;
+/// Share of generated non-base nodes that become waypoints instead of towers.
+const WAYPOINT_CHANCE: f32 = 0.25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapGenParams {
+    pub seed: u64,
+    /// Number of teams, at least 2. Every team gets one rotated copy of the map.
+    pub teams: u8,
+    /// Nodes per team including its base tower.
+    pub nodes_per_team: u16,
+    /// Chance in `0.0..=1.0` for each nearby node pair to get an extra edge on
+    /// top of the spanning tree.
+    pub density: f32,
+    /// Distance of the bases from the map center.
+    pub radius: f32,
+    /// Garrison given to every generated neutral tower
+    pub neutral: NeutralGarrison,
+}
+
+impl Default for MapGenParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            teams: 2,
+            nodes_per_team: 6,
+            density: 0.3,
+            radius: 500.0,
+            neutral: NeutralGarrison {
+                garrison: 10,
+                regen_rate: 1,
+                regen_cap: 15,
+                raid_below: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapGenError {
+    TooFewTeams(u8),
+    TooFewNodes(u16),
+    /// The node ids of the requested map would not fit into `NodeId`
+    TooManyNodes,
+    /// Only `placed` of the `requested` nodes per team fit into a sector at
+    /// the minimum spacing
+    CouldNotPlaceNodes { placed: u16, requested: u16 },
+    /// The generated map failed validation. This is a generator bug.
+    Invalid(Vec<MapValidationError>),
+}
+
+impl fmt::Display for MapGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewTeams(teams) => write!(f, "Need at least 2 teams, got {}", teams),
+            Self::TooFewNodes(nodes) => {
+                write!(f, "Need at least 2 nodes per team, got {}", nodes)
+            }
+            Self::TooManyNodes => write!(f, "Too many nodes for 16-bit node ids"),
+            Self::CouldNotPlaceNodes { placed, requested } => write!(
+                f,
+                "Could only place {} of {} nodes per team, use fewer nodes or a larger radius",
+                placed, requested
+            ),
+            Self::Invalid(errors) => write!(f, "Generated map is invalid: {:?}", errors),
+        }
+    }
+}
+
+impl std::error::Error for MapGenError {}
+
+/// SplitMix64, small and identical on every platform so a seed always
+/// reproduces the same map.
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `0.0..1.0`
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+
+    fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+}
+
+/// One node of the template sector, in polar coordinates relative to the
+/// sector's base direction.
+struct SectorNode {
+    radius: f32,
+    angle: f32,
+    is_waypoint: bool,
+}
+
+impl SectorNode {
+    fn position(&self, sector_angle: f32) -> Vec2 {
+        Vec2::from_angle(sector_angle + self.angle) * self.radius
+    }
+}
+
+/// Generates a map with rotational symmetry: one sector is generated from the
+/// seed and copied for every team, so all teams play the same layout.
+pub fn generate_map(params: &MapGenParams) -> Result<Map, MapGenError> {
+    if params.teams < 2 {
+        return Err(MapGenError::TooFewTeams(params.teams));
+    }
+    if params.nodes_per_team < 2 {
+        return Err(MapGenError::TooFewNodes(params.nodes_per_team));
+    }
+    let per_team = params.nodes_per_team as usize;
+    if per_team * params.teams as usize > NodeId::MAX as usize {
+        return Err(MapGenError::TooManyNodes);
+    }
+
+    let mut rng = SeededRng(params.seed);
+    let sector = TAU / params.teams as f32;
+    // Keep away from the sector borders so copies never overlap
+    let max_offset = sector * 0.4;
+
+    let mut template = vec![SectorNode {
+        radius: params.radius,
+        angle: 0.0,
+        is_waypoint: false,
+    }];
+    while template.len() < per_team {
+        let placed = (0..PLACEMENT_ATTEMPTS).find_map(|_| {
+            let candidate = SectorNode {
+                radius: rng.range(params.radius * 0.2, params.radius * 0.95),
+                angle: rng.range(-max_offset, max_offset),
+                is_waypoint: false,
+            };
+            let position = candidate.position(0.0);
+            let spaced = template
+                .iter()
+                .all(|node| node.position(0.0).distance(position) >= MIN_NODE_SPACING);
+            spaced.then_some(candidate)
+        });
+        let Some(mut node) = placed else {
+            return Err(MapGenError::CouldNotPlaceNodes {
+                placed: template.len() as u16,
+                requested: params.nodes_per_team,
+            });
+        };
+        node.is_waypoint = rng.chance(WAYPOINT_CHANCE);
+        template.push(node);
+    }
+
+    let template_edges = sector_edges(&template, params.density, &mut rng);
+
+    // Link each sector to the next through the nodes closest to the shared border
+    let leading = (0..template.len())
+        .max_by(|&a, &b| template[a].angle.total_cmp(&template[b].angle))
+        .unwrap_or(0);
+    let trailing = (0..template.len())
+        .min_by(|&a, &b| template[a].angle.total_cmp(&template[b].angle))
+        .unwrap_or(0);
+
+    let id = |team: usize, index: usize| (team * template.len() + index) as NodeId;
+    let mut nodes: HashMap<NodeId, MapNode> = HashMap::new();
+    for team in 0..params.teams as usize {
+        let sector_angle = team as f32 * sector;
+        for (index, node) in template.iter().enumerate() {
+            let (node_type, neutral) = if index == 0 {
+                (
+                    NodeType::StructureType(StructureType::BaseTower(team as TeamId + 1)),
+                    NeutralGarrison::NONE,
+                )
+            } else if node.is_waypoint {
+                (NodeType::Waypoint, NeutralGarrison::NONE)
+            } else {
+                (
+                    NodeType::StructureType(StructureType::Tower(None)),
+                    params.neutral,
+                )
+            };
+            nodes.insert(
+                id(team, index),
+                MapNode {
+                    id: id(team, index),
+                    connected_to: Vec::new(),
+                    position: node.position(sector_angle),
+                    node_type,
+                    neutral,
+                },
+            );
+        }
+    }
+
+    let mut connect = |a: NodeId, b: NodeId| {
+        for (from, to) in [(a, b), (b, a)] {
+            let node = nodes.get_mut(&from).expect("generated node exists");
+            if !node.connected_to.contains(&to) {
+                node.connected_to.push(to);
+            }
+        }
+    };
+    for team in 0..params.teams as usize {
+        for &(a, b) in &template_edges {
+            connect(id(team, a), id(team, b));
+        }
+        let next = (team + 1) % params.teams as usize;
+        connect(id(team, leading), id(next, trailing));
+    }
+
+    for node in nodes.values_mut() {
+        node.connected_to.sort_unstable();
+    }
+
+    let mut map = Map {
+        name: format!("Generated {}p #{}", params.teams, params.seed),
+        nodes,
+        edges: Vec::new(),
+    };
+    map.sync_edges();
+    map.validate().map_err(MapGenError::Invalid)?;
+    Ok(map)
+}
+
+/// A minimum spanning tree over the sector so it is always connected, plus
+/// random extra edges between nearby nodes.
+fn sector_edges(template: &[SectorNode], density: f32, rng: &mut SeededRng) -> Vec<(usize, usize)> {
+    let positions: Vec<Vec2> = template.iter().map(|node| node.position(0.0)).collect();
+    let mut edges = Vec::new();
+
+    // Prim's algorithm
+    let mut in_tree = vec![false; positions.len()];
+    in_tree[0] = true;
+    for _ in 1..positions.len() {
+        let closest = (0..positions.len())
+            .filter(|&a| in_tree[a])
+            .flat_map(|a| (0..positions.len()).filter(|&b| !in_tree[b]).map(move |b| (a, b)))
+            .min_by(|&(a1, b1), &(a2, b2)| {
+                positions[a1]
+                    .distance(positions[b1])
+                    .total_cmp(&positions[a2].distance(positions[b2]))
+            });
+        let Some((a, b)) = closest else {
+            break;
+        };
+        in_tree[b] = true;
+        edges.push((a, b));
+    }
+
+    let max_extra_length = MIN_NODE_SPACING * 4.0;
+    for a in 0..positions.len() {
+        for b in a + 1..positions.len() {
+            let exists = edges.contains(&(a, b)) || edges.contains(&(b, a));
+            if !exists
+                && positions[a].distance(positions[b]) <= max_extra_length
+                && rng.chance(density)
+            {
+                edges.push((a, b));
+            }
+        }
+    }
+
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_exactly_the_requested_nodes() {
+        let params = MapGenParams {
+            seed: 42,
+            teams: 3,
+            nodes_per_team: 8,
+            ..default()
+        };
+        let map = generate_map(&params).expect("map generates");
+        assert_eq!(map.nodes.len(), 3 * 8);
+        assert_eq!(map.base_towers().len(), 3);
+    }
+
+    #[test]
+    fn same_seed_generates_same_map() {
+        let params = MapGenParams {
+            seed: 7,
+            ..default()
+        };
+        assert_eq!(generate_map(&params), generate_map(&params));
+    }
+
+    #[test]
+    fn fails_when_the_nodes_do_not_fit() {
+        let params = MapGenParams {
+            nodes_per_team: 40,
+            radius: 100.0,
+            ..default()
+        };
+        assert!(matches!(
+            generate_map(&params),
+            Err(MapGenError::CouldNotPlaceNodes { requested: 40, .. })
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::gameplay::{
+    speed::GameTime,
+    state::{SimulationSet, run_if_game_running},
+};
+use crate::protocol::AppProtocolExt;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchPhase {
+    Regular,
+    /// Regular time is up. Towers no longer regenerate.
+    SuddenDeath,
+    /// Sudden death is over too and the match has to be decided
+    Expired,
+}
+
+/// Match time, spawned by the server when the match starts and replicated so
+/// clients can show a countdown.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct MatchClock {
+    pub elapsed: f32,
+    /// Length of regular time in seconds
+    pub duration: f32,
+    /// Length of sudden death in seconds
+    pub sudden_death: f32,
+}
+
+impl MatchClock {
+    pub fn new(duration: f32, sudden_death: f32) -> Self {
+        Self {
+            elapsed: 0.0,
+            duration,
+            sudden_death,
+        }
+    }
+
+    pub fn phase(&self) -> MatchPhase {
+        if self.elapsed < self.duration {
+            MatchPhase::Regular
+        } else if self.elapsed < self.duration + self.sudden_death {
+            MatchPhase::SuddenDeath
+        } else {
+            MatchPhase::Expired
+        }
+    }
+
+    /// Seconds left in the current phase
+    pub fn remaining(&self) -> f32 {
+        match self.phase() {
+            MatchPhase::Regular => self.duration - self.elapsed,
+            MatchPhase::SuddenDeath => self.duration + self.sudden_death - self.elapsed,
+            MatchPhase::Expired => 0.0,
+        }
+    }
+
+    pub fn is_sudden_death(&self) -> bool {
+        self.phase() == MatchPhase::SuddenDeath
+    }
+}
+
+fn tick_match_clock(time: GameTime, mut q_clock: Query<&mut MatchClock>) {
+    for mut clock in q_clock.iter_mut() {
+        let before = clock.phase();
+        clock.elapsed += time.delta_secs();
+        if before == MatchPhase::Regular && clock.phase() != MatchPhase::Regular {
+            info!("⏰ Time is up, sudden death for {}s", clock.sudden_death);
+        }
+    }
+}
+
+pub struct MatchClockPlugin;
+
+impl Plugin for MatchClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            tick_match_clock
+                .in_set(SimulationSet)
+                .run_if(run_if_game_running),
+        );
+        app.register_replicated::<MatchClock>("MatchClock");
+    }
+}
@@ -1,3 +1,14 @@
+pub mod abilities;
+pub mod balance;
+pub mod combat;
+pub mod display;
 pub mod map;
+pub mod map_analysis;
+pub mod map_gen;
+pub mod match_clock;
+pub mod routing;
+pub mod speed;
 pub mod state;
 pub mod structures;
+pub mod teams;
+pub mod troops;
@@ -0,0 +1,262 @@
+use crate::gameplay::map::{CurrentMap, Map, NodeId};
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// A path through the map graph together with its total length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// Nodes to travel through, including both endpoints.
+    pub nodes: Vec<NodeId>,
+    /// Sum of `Map::edge_cost` along the route, so terrain is accounted for.
+    pub distance: f32,
+}
+
+impl Route {
+    /// Seconds needed to walk the whole route at `speed` units per second.
+    pub fn travel_time(&self, speed: f32) -> f32 {
+        self.distance / speed
+    }
+}
+
+/// Shortest distances from a single source node, with the predecessor of every
+/// reached node so routes can be rebuilt.
+#[derive(Debug, Clone, Default)]
+pub struct ShortestPathTree {
+    pub source: NodeId,
+    distances: HashMap<NodeId, f32>,
+    previous: HashMap<NodeId, NodeId>,
+}
+
+impl ShortestPathTree {
+    pub fn distance_to(&self, target: NodeId) -> Option<f32> {
+        self.distances.get(&target).copied()
+    }
+
+    pub fn route_to(&self, target: NodeId) -> Option<Route> {
+        let distance = self.distance_to(target)?;
+        let mut nodes = vec![target];
+        let mut current = target;
+        while current != self.source {
+            current = *self.previous.get(&current)?;
+            nodes.push(current);
+        }
+        nodes.reverse();
+        Some(Route { nodes, distance })
+    }
+}
+
+#[derive(PartialEq)]
+struct QueueEntry {
+    distance: f32,
+    node: NodeId,
+}
+
+impl Eq for QueueEntry {}
+
+impl Ord for QueueEntry {
+    // Reversed so `BinaryHeap` pops the closest node first; ties go to the lower
+    // node id so routes are identical on every machine.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Map {
+    /// Dijkstra from `source` over all edges, weighted by `Map::edge_cost`.
+    pub fn shortest_paths_from(&self, source: NodeId) -> ShortestPathTree {
+        let mut tree = ShortestPathTree {
+            source,
+            ..default()
+        };
+        if self.get_node(source).is_none() {
+            return tree;
+        }
+
+        let mut queue = BinaryHeap::new();
+        tree.distances.insert(source, 0.0);
+        queue.push(QueueEntry {
+            distance: 0.0,
+            node: source,
+        });
+
+        while let Some(QueueEntry { distance, node }) = queue.pop() {
+            if tree.distances.get(&node).is_some_and(|&best| distance > best) {
+                continue;
+            }
+            let Some(map_node) = self.get_node(node) else {
+                continue;
+            };
+
+            let mut neighbours = map_node.connected_to.clone();
+            neighbours.sort_unstable();
+            for next in neighbours {
+                let Some(cost) = self.edge_cost(node, next) else {
+                    continue;
+                };
+                let candidate = distance + cost;
+                let improves = tree
+                    .distances
+                    .get(&next)
+                    .is_none_or(|&best| candidate < best);
+                if improves {
+                    tree.distances.insert(next, candidate);
+                    tree.previous.insert(next, node);
+                    queue.push(QueueEntry {
+                        distance: candidate,
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        tree
+    }
+
+    pub fn shortest_path(&self, from: NodeId, to: NodeId) -> Option<Route> {
+        self.shortest_paths_from(from).route_to(to)
+    }
+}
+
+/// All-pairs shortest paths for the current map, rebuilt whenever `CurrentMap` changes.
+#[derive(Resource, Debug, Default)]
+pub struct RouteTable {
+    trees: HashMap<NodeId, ShortestPathTree>,
+}
+
+impl RouteTable {
+    pub fn build(map: &Map) -> Self {
+        let trees = map
+            .nodes
+            .keys()
+            .map(|&id| (id, map.shortest_paths_from(id)))
+            .collect();
+        Self { trees }
+    }
+
+    pub fn route(&self, from: NodeId, to: NodeId) -> Option<Route> {
+        self.trees.get(&from)?.route_to(to)
+    }
+
+    pub fn distance(&self, from: NodeId, to: NodeId) -> Option<f32> {
+        self.trees.get(&from)?.distance_to(to)
+    }
+
+    pub fn is_reachable(&self, from: NodeId, to: NodeId) -> bool {
+        self.distance(from, to).is_some()
+    }
+}
+
+fn rebuild_route_table(
+    mut route_table: ResMut<RouteTable>,
+    q_map: Query<&CurrentMap, Changed<CurrentMap>>,
+) {
+    if let Ok(map) = q_map.single() {
+        *route_table = RouteTable::build(&map.0);
+        info!("Built route table for map '{}'", map.0.name);
+    }
+}
+
+pub struct RoutingPlugin;
+
+impl Plugin for RoutingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RouteTable>()
+            .add_systems(PreUpdate, rebuild_route_table);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gameplay::map::{MapEdge, MapFile, MapNode, NodeType};
+    use crate::gameplay::structures::NeutralGarrison;
+
+    fn node(id: NodeId, x: f32, y: f32, connected_to: &[NodeId]) -> MapNode {
+        MapNode {
+            id,
+            connected_to: connected_to.to_vec(),
+            position: Vec2::new(x, y),
+            node_type: NodeType::Waypoint,
+            neutral: NeutralGarrison::NONE,
+        }
+    }
+
+    fn map(nodes: Vec<MapNode>, edges: Vec<MapEdge>) -> Map {
+        Map::from_file_unchecked(MapFile {
+            name: "Test".to_string(),
+            nodes,
+            edges,
+        })
+    }
+
+    #[test]
+    fn takes_the_shortest_path() {
+        // The direct road from 1 to 3 is longer than the detour through 2
+        let map = map(
+            vec![
+                node(1, 0.0, 0.0, &[2, 3]),
+                node(2, 100.0, 0.0, &[1, 3]),
+                node(3, 200.0, 0.0, &[1, 2]),
+            ],
+            vec![MapEdge {
+                length: Some(500.0),
+                ..MapEdge::road(1, 3)
+            }],
+        );
+
+        let route = RouteTable::build(&map).route(1, 3).expect("3 is reachable");
+        assert_eq!(route.nodes, vec![1, 2, 3]);
+        assert_eq!(route.distance, 200.0);
+    }
+
+    #[test]
+    fn finds_no_path_between_separate_parts() {
+        let map = map(
+            vec![
+                node(1, 0.0, 0.0, &[2]),
+                node(2, 100.0, 0.0, &[1]),
+                node(3, 0.0, 100.0, &[4]),
+                node(4, 100.0, 100.0, &[3]),
+            ],
+            Vec::new(),
+        );
+
+        let routes = RouteTable::build(&map);
+        assert_eq!(routes.route(1, 4), None);
+        assert!(!routes.is_reachable(3, 2));
+        assert!(routes.is_reachable(3, 4));
+    }
+
+    #[test]
+    fn follows_one_way_edges_only_forwards() {
+        // 1 -> 2 is one-way, so going back from 2 has to pass 3
+        let map = map(
+            vec![
+                node(1, 0.0, 0.0, &[3]),
+                node(2, 100.0, 0.0, &[3]),
+                node(3, 50.0, 100.0, &[1, 2]),
+            ],
+            vec![MapEdge {
+                one_way: true,
+                ..MapEdge::road(1, 2)
+            }],
+        );
+
+        let routes = RouteTable::build(&map);
+        assert_eq!(routes.route(1, 2).map(|route| route.nodes), Some(vec![1, 2]));
+        assert_eq!(
+            routes.route(2, 1).map(|route| route.nodes),
+            Some(vec![2, 3, 1])
+        );
+    }
+}
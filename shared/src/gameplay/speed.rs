@@ -0,0 +1,58 @@
+use crate::protocol::AppProtocolExt;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const MIN_GAME_SPEED: f32 = 0.5;
+pub const MAX_GAME_SPEED: f32 = 8.0;
+
+/// Multiplier on gameplay time, set by the server and replicated on the game
+/// state entity. The network tick rate is not affected.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct GameSpeed(f32);
+
+impl GameSpeed {
+    /// Speeds outside `MIN_GAME_SPEED..=MAX_GAME_SPEED` are clamped into it.
+    pub fn new(speed: f32) -> Self {
+        if speed.is_nan() {
+            return Self::default();
+        }
+        Self(speed.clamp(MIN_GAME_SPEED, MAX_GAME_SPEED))
+    }
+
+    pub fn get(&self) -> f32 {
+        self.0
+    }
+}
+
+impl Default for GameSpeed {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Frame time scaled by the current `GameSpeed`. Every gameplay timer reads
+/// its delta from here instead of `Time`.
+#[derive(SystemParam)]
+pub struct GameTime<'w, 's> {
+    time: Res<'w, Time>,
+    speed: Query<'w, 's, &'static GameSpeed>,
+}
+
+impl GameTime<'_, '_> {
+    pub fn speed(&self) -> f32 {
+        self.speed.single().map_or(1.0, GameSpeed::get)
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.time.delta_secs() * self.speed()
+    }
+}
+
+pub struct GameSpeedPlugin;
+
+impl Plugin for GameSpeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_replicated::<GameSpeed>("GameSpeed");
+    }
+}
@@ -1,36 +1,54 @@
+use crate::protocol::AppProtocolExt;
 use bevy::prelude::*;
-use lightyear::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::GameNetworkChannel;
-
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GameState {
     Running,
     Paused,
+    Ended,
 }
 
+/// Spawned and replicated by the server, together with `PauseStatus`.
 #[derive(Component, Serialize, Deserialize, Clone, PartialEq)]
 pub struct CurrentGameState(pub GameState);
 
-pub fn run_if_game_running(game_state: Query<&CurrentGameState>) -> bool {
-    let Ok(state) = game_state.single() else {
-        error!("Run_if_game_running called before CurrentGameState exists");
-        return false;
-    };
-    state.0 == GameState::Running
+/// Who paused the game and, once someone asked to resume, the seconds left
+/// until it resumes.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct PauseStatus {
+    pub paused_by: Option<u32>,
+    pub resume_in: Option<f32>,
 }
 
-fn setup_game_state(mut commands: Commands) {
-    println!("Set game State!");
-    commands.spawn(CurrentGameState(GameState::Running));
+/// Inserted by the server, the only app that runs the simulation. Clients
+/// show the replicated results instead of simulating on their own.
+#[derive(Resource, Debug, Default)]
+pub struct SimulationAuthority;
+
+/// True on the server while the game is running. Clients never run the
+/// simulation, and a missing state means the server has not set one up yet.
+pub fn run_if_game_running(
+    authority: Option<Res<SimulationAuthority>>,
+    game_state: Query<&CurrentGameState>,
+) -> bool {
+    authority.is_some()
+        && game_state
+            .single()
+            .is_ok_and(|state| state.0 == GameState::Running)
 }
 
+/// The systems that advance the server simulation. Anything that changes
+/// towers or troops on behalf of a player runs before this set, so the
+/// simulation always sees the world it was validated against.
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SimulationSet;
+
 pub struct StatePlugin;
 
 impl Plugin for StatePlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_game_state);
-        app.register_component::<CurrentGameState>();
+        app.register_replicated::<CurrentGameState>("CurrentGameState");
+        app.register_replicated::<PauseStatus>("PauseStatus");
     }
 }
@@ -1,10 +1,15 @@
 use crate::gameplay::{
-    map::{Map, NodeId, NodeType},
-    state::{CurrentGameState, GameState, run_if_game_running},
+    abilities::{Frozen, ManaSurge},
+    balance::Balance,
+    map::NodeId,
+    match_clock::{MatchClock, MatchPhase},
+    speed::GameTime,
+    state::{SimulationAuthority, SimulationSet, run_if_game_running},
+    teams::Team,
     *,
 };
+use crate::protocol::AppProtocolExt;
 use bevy::prelude::*;
-use lightyear::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
@@ -23,14 +28,70 @@ pub struct Tower {
     pub owner: Option<TeamId>,
 }
 
+impl Tower {
+    /// Client-side blend between two server updates. Mana counts through the
+    /// values in between, ownership flips at the end.
+    pub fn interpolate(start: &Self, end: &Self, t: f32) -> Self {
+        let mana = start.mana as f32 + (end.mana as f32 - start.mana as f32) * t;
+        let owner = if t < 1.0 { start.owner } else { end.owner };
+        Self {
+            mana: mana.round() as u8,
+            node_id: end.node_id,
+            owner,
+        }
+    }
+}
+
 pub type TeamId = u8;
 
+/// Behaviour of a neutral tower, configured per map node. Only applies while
+/// the tower has no owner.
+#[derive(Component, Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct NeutralGarrison {
+    /// Mana the tower starts the match with
+    #[serde(default)]
+    pub garrison: u8,
+    /// Mana gained every regen interval while neutral
+    #[serde(default)]
+    pub regen_rate: u8,
+    /// Neutral regen stops at this amount
+    #[serde(default)]
+    pub regen_cap: u8,
+    /// When set, a neutral tower at its regen cap raids an adjacent owned tower
+    /// holding less than this much mana
+    #[serde(default)]
+    pub raid_below: Option<u8>,
+}
+
+impl NeutralGarrison {
+    /// An empty tower that never regenerates
+    pub const NONE: Self = Self {
+        garrison: 0,
+        regen_rate: 0,
+        regen_cap: 0,
+        raid_below: None,
+    };
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+/// Stats of a tower at its current level, taken from `Balance::tower_levels`
 #[derive(Component, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TowerStats {
-    level: u8,                // 1
-    max_mana: u8,             // 30
-    regen_rate: f32,          // 2
-    overflow_degen_rate: f32, // 6
+    level: u8,
+    max_mana: u8,
+    regen_rate: f32,
+    overflow_degen_rate: f32,
+}
+
+/// Present while a tower is being upgraded. The tower cannot send troops until
+/// the upgrade finishes and this component is removed.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TowerUpgrading {
+    pub target_level: u8,
+    pub remaining: f32,
 }
 
 #[derive(Component, Serialize, Deserialize, PartialEq)]
@@ -72,10 +133,16 @@ fn debug_tower_print(q_towers: Query<&Tower>) {
     }
 }
 
+/// Owned towers regenerate towards `max_mana` and decay back to it when
+/// overfilled. Neutral towers regenerate up to their `NeutralGarrison` cap.
+/// Frozen towers do not regenerate, and a team's `ManaSurge` doubles regen.
+/// Nothing regenerates once regular match time is over.
 fn generate_mana_for_captured_towers(
-    time: Res<Time>,
+    time: GameTime,
     mut gen_timer: Query<&mut TowerGenerationTimer>,
-    mut q_towers: Query<(&mut Tower, &TowerStats)>,
+    mut q_towers: Query<(&mut Tower, &TowerStats, Option<&NeutralGarrison>, Has<Frozen>)>,
+    q_surging: Query<&Team, With<ManaSurge>>,
+    q_clock: Query<&MatchClock>,
 ) {
     let Ok(mut timer) = gen_timer.get_single_mut() else {
         return;
@@ -84,11 +151,28 @@ fn generate_mana_for_captured_towers(
     timer.tick(time.delta_secs());
 
     if timer.finished() {
-        for (mut tower, stats) in q_towers.iter_mut() {
-            if tower.owner.is_some() {
-                if tower.mana < stats.max_mana() {
+        let surging: Vec<TeamId> = q_surging.iter().map(|team| team.id).collect();
+        let time_up = q_clock
+            .iter()
+            .any(|clock| clock.phase() != MatchPhase::Regular);
+        for (mut tower, stats, neutral, frozen) in q_towers.iter_mut() {
+            let can_regen = !frozen && !time_up;
+            if let (None, Some(neutral)) = (tower.owner, neutral) {
+                if tower.mana < neutral.regen_cap && can_regen {
+                    tower.mana = tower
+                        .mana
+                        .saturating_add(neutral.regen_rate)
+                        .min(neutral.regen_cap);
+                }
+            } else if let Some(owner) = tower.owner {
+                if tower.mana < stats.max_mana() && can_regen {
+                    let regen_rate = if surging.contains(&owner) {
+                        stats.regen_rate() * 2.0
+                    } else {
+                        stats.regen_rate()
+                    };
                     tower.mana =
-                        (tower.mana as f32 + stats.regen_rate()).min(stats.max_mana() as f32) as u8;
+                        (tower.mana as f32 + regen_rate).min(stats.max_mana() as f32) as u8;
                 } else if tower.mana > stats.max_mana() {
                     tower.mana = (tower.mana as f32 - stats.overflow_degen_rate())
                         .max(stats.max_mana() as f32) as u8;
@@ -103,23 +187,66 @@ fn generate_mana_for_captured_towers(
     }
 }
 
-pub struct TowerPlugin;
+fn progress_tower_upgrades(
+    mut commands: Commands,
+    time: GameTime,
+    balance: Res<Balance>,
+    mut q_upgrading: Query<(Entity, &Tower, &mut TowerStats, &mut TowerUpgrading)>,
+) {
+    for (entity, tower, mut stats, mut upgrading) in q_upgrading.iter_mut() {
+        upgrading.remaining -= time.delta_secs();
+        if upgrading.remaining <= 0.0 {
+            *stats = TowerStats::new(upgrading.target_level, &balance);
+            commands.entity(entity).remove::<TowerUpgrading>();
+            info!(
+                "Tower at node {} upgraded to level {}",
+                tower.node_id,
+                stats.level()
+            );
+        }
+    }
+}
 
-fn setup_tower_timer(mut commands: Commands) {
-    commands.spawn(TowerGenerationTimer::new(1.0));
+/// Re-applies balance values to existing towers and the generation timer,
+/// so hot-reloaded numbers take effect mid-match.
+fn apply_balance_to_towers(
+    balance: Res<Balance>,
+    mut q_timer: Query<&mut TowerGenerationTimer>,
+    mut q_stats: Query<&mut TowerStats>,
+) {
+    for mut timer in q_timer.iter_mut() {
+        timer.duration = balance.regen_interval;
+    }
+    for mut stats in q_stats.iter_mut() {
+        let updated = TowerStats::new(stats.level(), &balance);
+        if *stats != updated {
+            *stats = updated;
+        }
+    }
 }
 
+pub struct TowerPlugin;
+
 impl Plugin for TowerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_tower_timer).add_systems(
+        app.add_systems(
+            Update,
+            (generate_mana_for_captured_towers, progress_tower_upgrades)
+                .in_set(SimulationSet)
+                .run_if(run_if_game_running),
+        );
+        app.add_systems(
             Update,
-            generate_mana_for_captured_towers.run_if(run_if_game_running),
+            apply_balance_to_towers
+                .run_if(resource_exists::<SimulationAuthority>.and(resource_changed::<Balance>)),
         );
         app.add_systems(FixedUpdate, debug_tower_print);
-        app.register_component::<Tower>();
-        app.register_component::<TowerStats>();
-        app.register_component::<BaseTowerMarker>();
-        app.register_component::<TowerGenerationTimer>();
+        app.register_interpolated::<Tower>("Tower", Tower::interpolate);
+        app.register_replicated::<TowerStats>("TowerStats");
+        app.register_replicated::<BaseTowerMarker>("BaseTowerMarker");
+        app.register_replicated::<TowerGenerationTimer>("TowerGenerationTimer");
+        app.register_replicated::<TowerUpgrading>("TowerUpgrading");
+        app.register_replicated::<NeutralGarrison>("NeutralGarrison");
     }
 }
 
@@ -140,15 +267,16 @@ impl TowerStats {
         self.overflow_degen_rate
     }
 
-    pub fn new(level: u8) -> Self {
-        match level {
-            1 => TowerStats {
-                level: 1,
-                max_mana: 30,
-                regen_rate: 2.0,
-                overflow_degen_rate: 6.0,
-            },
-            _ => panic!("Unsupported tower level: {}", level),
+    /// Stats for a tower of the given level. Levels outside the balance table
+    /// are clamped into it.
+    pub fn new(level: u8, balance: &Balance) -> Self {
+        let level = level.clamp(1, balance.max_tower_level());
+        let entry = balance.tower_level(level);
+        TowerStats {
+            level,
+            max_mana: entry.max_mana,
+            regen_rate: entry.regen_rate,
+            overflow_degen_rate: entry.overflow_degen_rate,
         }
     }
 }
@@ -0,0 +1,100 @@
+use crate::gameplay::structures::TeamId;
+use crate::protocol::AppProtocolExt;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Display colours handed out to teams in id order.
+pub const TEAM_COLORS: [[u8; 3]; 8] = [
+    [231, 76, 60],
+    [52, 152, 219],
+    [46, 204, 113],
+    [241, 196, 15],
+    [155, 89, 182],
+    [230, 126, 34],
+    [26, 188, 156],
+    [236, 240, 241],
+];
+
+/// A team taking part in the match. Spawned and replicated by the server.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Team {
+    pub id: TeamId,
+    /// sRGB display colour
+    pub color: [u8; 3],
+    /// Backend player ids playing for this team
+    pub members: Vec<u32>,
+    /// Teams this team is allied with. Alliances are symmetric.
+    pub allies: Vec<TeamId>,
+}
+
+impl Team {
+    pub fn new(id: TeamId) -> Self {
+        Self {
+            id,
+            color: TEAM_COLORS[(id as usize).saturating_sub(1) % TEAM_COLORS.len()],
+            members: Vec::new(),
+            allies: Vec::new(),
+        }
+    }
+}
+
+/// Lookup of all `Team` entities by id, rebuilt whenever a team changes.
+#[derive(Resource, Debug, Default)]
+pub struct TeamRegistry {
+    teams: BTreeMap<TeamId, Team>,
+}
+
+impl TeamRegistry {
+    pub fn get(&self, id: TeamId) -> Option<&Team> {
+        self.teams.get(&id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Team> {
+        self.teams.values()
+    }
+
+    /// A team is always allied with itself.
+    pub fn are_allied(&self, a: TeamId, b: TeamId) -> bool {
+        a == b
+            || self
+                .teams
+                .get(&a)
+                .is_some_and(|team| team.allies.contains(&b))
+    }
+
+    /// Whether `team` may treat something owned by `owner` as friendly.
+    /// Neutral (`None`) is never friendly.
+    pub fn is_friendly(&self, team: TeamId, owner: Option<TeamId>) -> bool {
+        owner.is_some_and(|owner| self.are_allied(team, owner))
+    }
+
+    pub fn team_of_player(&self, player_id: u32) -> Option<TeamId> {
+        self.teams
+            .values()
+            .find(|team| team.members.contains(&player_id))
+            .map(|team| team.id)
+    }
+}
+
+fn rebuild_team_registry(
+    mut registry: ResMut<TeamRegistry>,
+    q_teams: Query<&Team>,
+    q_changed: Query<(), Changed<Team>>,
+    mut removed: RemovedComponents<Team>,
+) {
+    if q_changed.is_empty() && removed.read().next().is_none() {
+        return;
+    }
+    registry.teams = q_teams.iter().map(|team| (team.id, team.clone())).collect();
+}
+
+pub struct TeamPlugin;
+
+impl Plugin for TeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TeamRegistry>()
+            .add_systems(PreUpdate, rebuild_team_registry);
+        app.register_replicated::<Team>("Team");
+    }
+}
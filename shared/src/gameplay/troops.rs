@@ -0,0 +1,181 @@
+use crate::gameplay::{
+    balance::Balance,
+    map::{CurrentMap, Map, NodeId},
+    speed::GameTime,
+    state::{SimulationSet, run_if_game_running},
+    structures::TeamId,
+};
+use crate::protocol::AppProtocolExt;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A group of troops travelling along the map graph.
+#[derive(Component, Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TroopGroup {
+    pub owner: TeamId,
+    pub amount: u8,
+    /// Nodes the group travels through, starting at the source tower.
+    pub path: Vec<NodeId>,
+    /// Index into `path` of the node the group last left.
+    pub segment: usize,
+    /// Distance travelled along the current edge.
+    pub progress: f32,
+    /// Length of the current edge, kept up to date by the server so clients
+    /// can interpolate across edges without the map.
+    pub segment_length: f32,
+}
+
+impl TroopGroup {
+    pub fn new(owner: TeamId, amount: u8, path: Vec<NodeId>) -> Self {
+        Self {
+            owner,
+            amount,
+            path,
+            segment: 0,
+            progress: 0.0,
+            segment_length: 0.0,
+        }
+    }
+
+    pub fn origin(&self) -> Option<NodeId> {
+        self.path.first().copied()
+    }
+
+    pub fn destination(&self) -> Option<NodeId> {
+        self.path.last().copied()
+    }
+
+    /// The edge the group is currently on, or `None` once it has arrived.
+    pub fn current_edge(&self) -> Option<(NodeId, NodeId)> {
+        let from = *self.path.get(self.segment)?;
+        let to = *self.path.get(self.segment + 1)?;
+        Some((from, to))
+    }
+
+    /// Client-side blend between two server updates. Within one edge the
+    /// progress is blended. When the group moved on to the next edge, the
+    /// distance left on the old edge and the progress on the new one are
+    /// covered together, so the group does not jump to the corner.
+    pub fn interpolate(start: &Self, end: &Self, t: f32) -> Self {
+        if start.segment == end.segment {
+            return Self {
+                progress: start.progress + (end.progress - start.progress) * t,
+                ..end.clone()
+            };
+        }
+        if end.segment != start.segment + 1 {
+            // Skipped whole edges whose length is unknown here
+            return Self {
+                progress: end.progress * t,
+                ..end.clone()
+            };
+        }
+
+        let left_on_start = (start.segment_length - start.progress).max(0.0);
+        let travelled = (left_on_start + end.progress) * t;
+        if travelled < left_on_start {
+            Self {
+                segment: start.segment,
+                progress: start.progress + travelled,
+                segment_length: start.segment_length,
+                ..end.clone()
+            }
+        } else {
+            Self {
+                progress: travelled - left_on_start,
+                ..end.clone()
+            }
+        }
+    }
+
+    /// World position of the group, interpolated along its current edge.
+    pub fn position(&self, map: &Map) -> Option<Vec2> {
+        let Some((from, to)) = self.current_edge() else {
+            return self
+                .destination()
+                .and_then(|id| map.get_node(id))
+                .map(|node| node.position);
+        };
+        let from_pos = map.get_node(from)?.position;
+        let to_pos = map.get_node(to)?.position;
+        let length = map.edge_length(from, to)?;
+        if length <= f32::EPSILON {
+            return Some(to_pos);
+        }
+        Some(from_pos.lerp(to_pos, (self.progress / length).clamp(0.0, 1.0)))
+    }
+}
+
+/// Sent when a troop group reaches the last node of its path.
+/// The group entity is despawned in the same frame.
+#[derive(Event, Clone, Debug)]
+pub struct TroopGroupArrived {
+    pub group: TroopGroup,
+    pub node_id: NodeId,
+}
+
+pub fn move_troop_groups(
+    mut commands: Commands,
+    time: GameTime,
+    balance: Res<Balance>,
+    q_map: Query<&CurrentMap>,
+    mut q_groups: Query<(Entity, &mut TroopGroup)>,
+    mut arrived: EventWriter<TroopGroupArrived>,
+) {
+    let Ok(map) = q_map.single() else {
+        return;
+    };
+
+    'groups: for (entity, mut group) in q_groups.iter_mut() {
+        let mut remaining = balance.troop_speed * time.delta_secs();
+
+        while let Some((from, to)) = group.current_edge() {
+            let Some(length) = map.0.edge_length(from, to) else {
+                warn!(
+                    "Troop group {:?} is on missing edge {} -> {}, removing it",
+                    entity, from, to
+                );
+                commands.entity(entity).despawn();
+                continue 'groups;
+            };
+
+            // `remaining` is road distance, scale it by this edge's terrain
+            let multiplier = map.0.edge_speed_multiplier(from, to);
+            let step = remaining * multiplier;
+            if group.progress + step < length {
+                group.progress += step;
+                group.segment_length = length;
+                break;
+            }
+
+            remaining -= (length - group.progress) / multiplier;
+            group.segment += 1;
+            group.progress = 0.0;
+        }
+
+        if group.current_edge().is_none() {
+            if let Some(node_id) = group.destination() {
+                arrived.write(TroopGroupArrived {
+                    group: group.clone(),
+                    node_id,
+                });
+            }
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+pub struct TroopPlugin;
+
+impl Plugin for TroopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TroopGroupArrived>();
+        app.add_systems(
+            Update,
+            move_troop_groups
+                .in_set(SimulationSet)
+                .run_if(run_if_game_running),
+        );
+        app.register_interpolated::<TroopGroup>("TroopGroup", TroopGroup::interpolate);
+    }
+}
@@ -0,0 +1,438 @@
+//! Every message exchanged between client and server, and the channels they
+//! travel on. Gameplay commands and notifications use `CommandChannel`, chat
+//! uses `ChatChannel` and pings use `PingChannel`.
+//!
+//! Channels, messages and replicated components are registered through `AppProtocolExt`,
+//! which records them in the `ProtocolManifest`. Its hash is the protocol
+//! version that client and server compare on connect.
+
+use bevy::ecs::component::Mutable;
+use bevy::prelude::*;
+use lightyear::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::gameplay::{abilities::Ability, balance::Balance, map::NodeId, structures::TeamId};
+
+/// Netcode protocol id. Deliberately fixed: a client with a different protocol
+/// version must still be able to connect, so it can be told why it is refused.
+pub const NETCODE_PROTOCOL_ID: u64 = 15;
+
+/// Bump when a message or component changes its fields without changing its
+/// name, so the protocol version changes too.
+pub const PROTOCOL_REVISION: u32 = 2;
+
+/// Every registered channel, message and replicated component, in registration order.
+/// Lightyear assigns network ids by that order, so it is part of the version.
+#[derive(Resource, Default, Debug)]
+pub struct ProtocolManifest {
+    entries: Vec<String>,
+}
+
+impl ProtocolManifest {
+    /// FNV-1a hash of the revision and the entries. Unlike `DefaultHasher` it
+    /// is the same across builds and platforms.
+    pub fn version(&self) -> u64 {
+        let entries = self.entries.iter().flat_map(|entry| entry.bytes().chain([0]));
+        PROTOCOL_REVISION
+            .to_le_bytes()
+            .into_iter()
+            .chain(entries)
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+}
+
+/// Registers network types with lightyear and records them in the `ProtocolManifest`.
+///
+/// `name` is what goes into the manifest. Rust type paths are not stable across
+/// compiler versions or module moves, so every type gets an explicit name;
+/// keep it when only the Rust type is renamed or moved.
+pub trait AppProtocolExt {
+    /// Channel settings are part of the entry, since both ends must agree on them
+    fn add_protocol_channel<C: Channel>(
+        &mut self,
+        name: &'static str,
+        settings: ChannelSettings,
+        direction: NetworkDirection,
+    ) -> &mut Self;
+
+    fn add_protocol_message<M: Message + Serialize + DeserializeOwned>(
+        &mut self,
+        name: &'static str,
+        direction: NetworkDirection,
+    ) -> &mut Self;
+
+    fn register_replicated<
+        C: Component<Mutability = Mutable> + PartialEq + Serialize + DeserializeOwned,
+    >(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self;
+
+    /// Like `register_replicated`, and clients blend between the last two
+    /// server updates with `interpolation` on their `Interpolated` copy.
+    fn register_interpolated<
+        C: Component<Mutability = Mutable> + Clone + PartialEq + Serialize + DeserializeOwned,
+    >(
+        &mut self,
+        name: &'static str,
+        interpolation: fn(&C, &C, f32) -> C,
+    ) -> &mut Self;
+}
+
+impl AppProtocolExt for App {
+    fn add_protocol_channel<C: Channel>(
+        &mut self,
+        name: &'static str,
+        settings: ChannelSettings,
+        direction: NetworkDirection,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ProtocolManifest>()
+            .entries
+            .push(format!("channel {} {:?} {:?}", name, settings.mode, direction));
+        self.add_channel::<C>(settings).add_direction(direction);
+        self
+    }
+
+    fn add_protocol_message<M: Message + Serialize + DeserializeOwned>(
+        &mut self,
+        name: &'static str,
+        direction: NetworkDirection,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ProtocolManifest>()
+            .entries
+            .push(format!("message {}", name));
+        self.add_message::<M>().add_direction(direction);
+        self
+    }
+
+    fn register_replicated<
+        C: Component<Mutability = Mutable> + PartialEq + Serialize + DeserializeOwned,
+    >(
+        &mut self,
+        name: &'static str,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ProtocolManifest>()
+            .entries
+            .push(format!("component {}", name));
+        self.register_component::<C>();
+        self
+    }
+
+    fn register_interpolated<
+        C: Component<Mutability = Mutable> + Clone + PartialEq + Serialize + DeserializeOwned,
+    >(
+        &mut self,
+        name: &'static str,
+        interpolation: fn(&C, &C, f32) -> C,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_init::<ProtocolManifest>()
+            .entries
+            .push(format!("interpolated component {}", name));
+        self.register_component::<C>()
+            .add_interpolation(InterpolationMode::Full)
+            .add_interpolation_fn(interpolation);
+        self
+    }
+}
+
+/// Gameplay commands and server notifications. Ordered, so commands of one
+/// kind are validated in the order they were sent. Lightyear receives each
+/// kind separately, so commands of different kinds arriving in the same
+/// frame are validated troop dispatches first, then upgrades, then abilities.
+pub struct CommandChannel;
+
+/// Chat between players. Reliable, but a late line must not hold up the others.
+pub struct ChatChannel;
+
+/// Cosmetic pings, fine to lose.
+pub struct PingChannel;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PingMessage(pub String);
+
+// Handshake
+
+/// First message of every client, carrying its protocol version.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClientHello {
+    pub version: u64,
+}
+
+/// Server reply to a `ClientHello` with the wrong version, right before it
+/// disconnects the client.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VersionMismatch {
+    pub server: u64,
+    pub client: u64,
+}
+
+// Client → server commands
+
+/// Client request to send `amount` mana from the tower at `from` towards `to`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SendTroops {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub amount: u8,
+}
+
+/// Client request to upgrade the tower at `node_id` to its next level.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UpgradeTower {
+    pub node_id: NodeId,
+}
+
+/// Client request to cast `ability`, paying its mana from the tower at `source`.
+/// `target` is the tower to freeze or shield and is ignored for `ManaSurge`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CastAbility {
+    pub ability: Ability,
+    pub source: NodeId,
+    pub target: Option<NodeId>,
+}
+
+/// Client request to pause the match, paid from the player's pause budget.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RequestPause;
+
+/// Client request to resume a paused match after the unpause countdown.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RequestResume;
+
+/// Client chat line. The server does not handle it yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatMessage {
+    pub text: String,
+}
+
+/// Client request to give up. The server does not handle it yet.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Surrender;
+
+// Server → client notifications
+
+/// Lifecycle of a match on the server. Each transition is broadcast as
+/// `MatchStateChanged`.
+#[derive(States, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum MatchState {
+    #[default]
+    WaitingForPlayers,
+    MatchStarting,
+    InProgress,
+    Completed,
+}
+
+/// Server notification that the match entered `state`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchStateChanged {
+    pub state: MatchState,
+}
+
+/// Server notification that the match has begun, telling each client its team.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchStarted {
+    pub match_id: u32,
+    pub team: TeamId,
+}
+
+/// Server notification carrying the balance values used for this match.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BalanceUpdate(pub Balance);
+
+/// Why the server refused a client command.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum RejectionReason {
+    MatchNotRunning,
+    /// The sender is not assigned to a team on this map
+    NoTeam,
+    UnknownNode(NodeId),
+    NoTower(NodeId),
+    NotYourTower(NodeId),
+    TowerUpgrading(NodeId),
+    MaxLevel(NodeId),
+    NotEnoughMana {
+        node: NodeId,
+        required: u8,
+        available: u8,
+    },
+    InvalidAmount,
+    Unreachable {
+        from: NodeId,
+        to: NodeId,
+    },
+    OnCooldown {
+        ability: Ability,
+        remaining: f32,
+    },
+    InvalidTarget {
+        ability: Ability,
+        target: Option<NodeId>,
+    },
+}
+
+impl fmt::Display for RejectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MatchNotRunning => write!(f, "The match is not running"),
+            Self::NoTeam => write!(f, "You are not on a team"),
+            Self::UnknownNode(node) => write!(f, "Node {} does not exist", node),
+            Self::NoTower(node) => write!(f, "There is no tower at node {}", node),
+            Self::NotYourTower(node) => write!(f, "Tower {} is not yours", node),
+            Self::TowerUpgrading(node) => write!(f, "Tower {} is upgrading", node),
+            Self::MaxLevel(node) => write!(f, "Tower {} is already at the maximum level", node),
+            Self::NotEnoughMana {
+                node,
+                required,
+                available,
+            } => write!(
+                f,
+                "Tower {} needs {} mana but only has {}",
+                node, required, available
+            ),
+            Self::InvalidAmount => write!(f, "Troop amount must be at least 1"),
+            Self::Unreachable { from, to } => {
+                write!(f, "Node {} cannot be reached from node {}", to, from)
+            }
+            Self::OnCooldown { ability, remaining } => {
+                write!(f, "{:?} is ready in {:.0}s", ability, remaining.ceil())
+            }
+            Self::InvalidTarget { ability, target } => match target {
+                Some(target) => write!(f, "{:?} cannot target tower {}", ability, target),
+                None => write!(f, "{:?} needs a target", ability),
+            },
+        }
+    }
+}
+
+/// Server reply to a command that failed validation. Nothing was applied.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CommandRejected {
+    pub reason: RejectionReason,
+}
+
+/// Something that happened in the match, for the client to show or play a sound for.
+/// The resulting state arrives separately through replication. The server
+/// collects them as Bevy events and broadcasts them once per frame.
+#[derive(Event, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum GameplayEvent {
+    TowerCaptured {
+        node_id: NodeId,
+        previous_owner: Option<TeamId>,
+        new_owner: TeamId,
+    },
+    TowerRaided {
+        raider: NodeId,
+        node_id: NodeId,
+        previous_owner: TeamId,
+        captured: bool,
+    },
+    TroopsClashed {
+        edge: (NodeId, NodeId),
+        teams: (TeamId, TeamId),
+        losses: u8,
+    },
+    AbilityCast {
+        team: TeamId,
+        ability: Ability,
+        target: Option<NodeId>,
+    },
+    /// Not sent yet, since the server does not handle `Surrender`
+    TeamSurrendered {
+        team: TeamId,
+    },
+}
+
+/// A chat line from another player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ChatBroadcast {
+    pub player_id: u32,
+    pub team: TeamId,
+    pub text: String,
+}
+
+/// Server notification that the match is over. `winners` holds every team of
+/// the winning alliance and is empty on a draw.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchEnded {
+    pub winners: Vec<TeamId>,
+}
+
+/// Sends the `ClientHello` as soon as the connection is up. Clients add this
+/// system themselves, the server never connects to anyone.
+pub fn send_client_hello(
+    mut q_senders: Query<&mut MessageSender<ClientHello>, Added<Connected>>,
+    manifest: Res<ProtocolManifest>,
+) {
+    for mut sender in q_senders.iter_mut() {
+        info!("Sending protocol version {:016x}", manifest.version());
+        sender.send::<CommandChannel>(ClientHello {
+            version: manifest.version(),
+        });
+    }
+}
+
+pub struct ProtocolPlugin;
+
+impl Plugin for ProtocolPlugin {
+    fn build(&self, app: &mut App) {
+        use NetworkDirection::{Bidirectional, ClientToServer, ServerToClient};
+
+        // The handshake and its channel go first, so their network ids stay the
+        // same whatever changes further down
+        app.add_protocol_channel::<CommandChannel>(
+            "CommandChannel",
+            ChannelSettings {
+                mode: ChannelMode::OrderedReliable(ReliableSettings::default()),
+                ..default()
+            },
+            Bidirectional,
+        );
+        app.add_protocol_message::<ClientHello>("ClientHello", ClientToServer)
+            .add_protocol_message::<VersionMismatch>("VersionMismatch", ServerToClient);
+
+        app.add_protocol_channel::<ChatChannel>(
+            "ChatChannel",
+            ChannelSettings {
+                mode: ChannelMode::UnorderedReliable(ReliableSettings::default()),
+                ..default()
+            },
+            Bidirectional,
+        )
+        .add_protocol_channel::<PingChannel>(
+            "PingChannel",
+            ChannelSettings {
+                mode: ChannelMode::UnorderedUnreliable,
+                ..default()
+            },
+            Bidirectional,
+        );
+
+        app.add_protocol_message::<PingMessage>("PingMessage", Bidirectional);
+
+        // Client → server
+        app.add_protocol_message::<SendTroops>("SendTroops", ClientToServer)
+            .add_protocol_message::<UpgradeTower>("UpgradeTower", ClientToServer)
+            .add_protocol_message::<CastAbility>("CastAbility", ClientToServer)
+            .add_protocol_message::<RequestPause>("RequestPause", ClientToServer)
+            .add_protocol_message::<RequestResume>("RequestResume", ClientToServer)
+            .add_protocol_message::<ChatMessage>("ChatMessage", ClientToServer)
+            .add_protocol_message::<Surrender>("Surrender", ClientToServer);
+
+        // Server → client
+        app.add_protocol_message::<MatchStateChanged>("MatchStateChanged", ServerToClient)
+            .add_protocol_message::<MatchStarted>("MatchStarted", ServerToClient)
+            .add_protocol_message::<BalanceUpdate>("BalanceUpdate", ServerToClient)
+            .add_protocol_message::<CommandRejected>("CommandRejected", ServerToClient)
+            .add_protocol_message::<GameplayEvent>("GameplayEvent", ServerToClient)
+            .add_protocol_message::<ChatBroadcast>("ChatBroadcast", ServerToClient)
+            .add_protocol_message::<MatchEnded>("MatchEnded", ServerToClient);
+    }
+}